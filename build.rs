@@ -0,0 +1,4 @@
+// 迁移脚本通过 sqlx::migrate! 内嵌，修改后需要重新编译
+fn main() {
+    println!("cargo:rerun-if-changed=migrations");
+}
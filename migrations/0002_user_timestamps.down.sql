@@ -0,0 +1,5 @@
+DROP INDEX IF EXISTS users_created_at_id_idx;
+
+ALTER TABLE users
+    DROP COLUMN updated_at,
+    ALTER COLUMN created_at DROP NOT NULL;
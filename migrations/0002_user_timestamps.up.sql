@@ -0,0 +1,10 @@
+UPDATE users SET created_at = CURRENT_TIMESTAMP WHERE created_at IS NULL;
+
+ALTER TABLE users
+    ALTER COLUMN created_at SET NOT NULL,
+    ADD COLUMN updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP;
+
+UPDATE users SET updated_at = created_at;
+
+-- GET /api/users 默认按 created_at 倒序分页
+CREATE INDEX users_created_at_id_idx ON users (created_at, id);
@@ -0,0 +1,2 @@
+-- 乐观并发控制：每次更新递增，对外以 ETag 形式暴露
+ALTER TABLE users ADD COLUMN version INTEGER NOT NULL DEFAULT 1;
@@ -0,0 +1,7 @@
+-- 回滚前先清除已软删除的用户，否则无法恢复邮箱上的唯一约束
+DELETE FROM users WHERE deleted_at IS NOT NULL;
+
+DROP INDEX users_deleted_at_idx;
+DROP INDEX users_email_active_key;
+ALTER TABLE users ADD CONSTRAINT users_email_key UNIQUE (email);
+ALTER TABLE users DROP COLUMN deleted_at;
@@ -0,0 +1,8 @@
+-- 软删除：deleted_at 非空的用户对常规查询不可见，超过保留期后由管理员清除
+ALTER TABLE users ADD COLUMN deleted_at TIMESTAMP WITH TIME ZONE;
+
+-- 邮箱只在未删除的用户之间唯一，已删除用户的邮箱可被重新注册
+ALTER TABLE users DROP CONSTRAINT users_email_key;
+CREATE UNIQUE INDEX users_email_active_key ON users (email) WHERE deleted_at IS NULL;
+
+CREATE INDEX users_deleted_at_idx ON users (deleted_at) WHERE deleted_at IS NOT NULL;
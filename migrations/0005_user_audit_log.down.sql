@@ -0,0 +1,2 @@
+DROP TABLE user_audit_log;
+DROP FUNCTION user_audit_log_append_only();
@@ -0,0 +1,25 @@
+-- 用户变更的审计日志，与变更在同一事务中写入；不设外键，用户被清除后记录仍保留
+CREATE TABLE user_audit_log (
+    id BIGSERIAL PRIMARY KEY,
+    user_id INTEGER NOT NULL,
+    action TEXT NOT NULL,
+    actor TEXT,
+    request_id TEXT,
+    -- 只包含发生变化的字段
+    before JSONB,
+    after JSONB,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX user_audit_log_user_id_idx ON user_audit_log (user_id, id);
+
+-- 只允许追加
+CREATE FUNCTION user_audit_log_append_only() RETURNS trigger AS $$
+BEGIN
+    RAISE EXCEPTION 'user_audit_log is append-only';
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER user_audit_log_append_only
+    BEFORE UPDATE OR DELETE OR TRUNCATE ON user_audit_log
+    FOR EACH STATEMENT EXECUTE FUNCTION user_audit_log_append_only();
@@ -0,0 +1,32 @@
+-- 用户事件的 outbox，与变更在同一事务中写入，由 webhook 分发器异步投递
+CREATE TABLE user_outbox (
+    id BIGSERIAL PRIMARY KEY,
+    event_id UUID NOT NULL UNIQUE,
+    event_type TEXT NOT NULL,
+    user_id INTEGER NOT NULL,
+    payload JSONB NOT NULL,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    -- 已为各端点生成投递任务的时间
+    dispatched_at TIMESTAMP WITH TIME ZONE
+);
+
+CREATE INDEX user_outbox_pending_idx ON user_outbox (id) WHERE dispatched_at IS NULL;
+
+-- 每个事件在每个订阅端点上的投递状态
+CREATE TABLE webhook_deliveries (
+    id BIGSERIAL PRIMARY KEY,
+    outbox_id BIGINT NOT NULL REFERENCES user_outbox (id) ON DELETE CASCADE,
+    endpoint TEXT NOT NULL,
+    -- pending、delivered 或 dead（重试耗尽）
+    status TEXT NOT NULL DEFAULT 'pending'
+        CHECK (status IN ('pending', 'delivered', 'dead')),
+    attempts INTEGER NOT NULL DEFAULT 0,
+    next_attempt_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    last_error TEXT,
+    delivered_at TIMESTAMP WITH TIME ZONE,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    UNIQUE (outbox_id, endpoint)
+);
+
+CREATE INDEX webhook_deliveries_pending_idx
+    ON webhook_deliveries (next_attempt_at) WHERE status = 'pending';
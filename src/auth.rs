@@ -0,0 +1,119 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{FromRequestParts, Request, State},
+    http::{header, request::Parts},
+    middleware::Next,
+    response::Response,
+};
+use jsonwebtoken::{errors::ErrorKind, Algorithm, DecodingKey, Validation};
+use serde::{Deserialize, Serialize};
+
+use crate::error::ApiError;
+
+// JWT 载荷
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Claims {
+    pub sub: String,
+    pub exp: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub roles: Vec<String>,
+}
+
+// 已认证的调用方，由 require_auth 注入到请求扩展中
+#[derive(Debug, Clone, Serialize)]
+pub struct Principal {
+    pub subject: String,
+    pub name: Option<String>,
+    pub roles: Vec<String>,
+}
+
+impl Principal {
+    pub fn has_role(&self, role: &str) -> bool {
+        self.roles.iter().any(|r| r == role)
+    }
+
+    // 缺少角色时返回 403
+    pub fn require_role(&self, role: &str) -> Result<(), ApiError> {
+        if self.has_role(role) {
+            Ok(())
+        } else {
+            Err(ApiError::Forbidden(format!("Requires the {} role", role)))
+        }
+    }
+}
+
+impl<S: Send + Sync> FromRequestParts<S> for Principal {
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        parts
+            .extensions
+            .get::<Principal>()
+            .cloned()
+            .ok_or_else(|| ApiError::Unauthorized("Missing bearer token".to_string()))
+    }
+}
+
+// HS256 令牌校验
+#[derive(Clone)]
+pub struct JwtAuth {
+    key: Arc<DecodingKey>,
+    validation: Arc<Validation>,
+}
+
+impl JwtAuth {
+    pub fn new(secret: &[u8]) -> Self {
+        Self {
+            key: Arc::new(DecodingKey::from_secret(secret)),
+            validation: Arc::new(Validation::new(Algorithm::HS256)),
+        }
+    }
+
+    pub fn verify(&self, token: &str) -> Result<Principal, ApiError> {
+        let data = jsonwebtoken::decode::<Claims>(token, &self.key, &self.validation).map_err(
+            |err| match err.kind() {
+                ErrorKind::ExpiredSignature => ApiError::InvalidToken("Token has expired".to_string()),
+                _ => ApiError::InvalidToken("Token is invalid".to_string()),
+            },
+        )?;
+
+        Ok(Principal {
+            subject: data.claims.sub,
+            name: data.claims.name,
+            roles: data.claims.roles,
+        })
+    }
+}
+
+// 认证中间件：校验 Authorization: Bearer <token>，失败时返回 401。
+// 已通过 mTLS 客户端证书认证的连接不再需要令牌
+pub async fn require_auth(
+    State(auth): State<JwtAuth>,
+    mut req: Request,
+    next: Next,
+) -> Result<Response, ApiError> {
+    if req.extensions().get::<Principal>().is_some() {
+        return Ok(next.run(req).await);
+    }
+
+    let header_value = req
+        .headers()
+        .get(header::AUTHORIZATION)
+        .ok_or_else(|| ApiError::Unauthorized("Missing bearer token".to_string()))?;
+
+    let token = header_value
+        .to_str()
+        .ok()
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .map(str::trim)
+        .filter(|token| !token.is_empty())
+        .ok_or_else(|| ApiError::InvalidToken("Malformed Authorization header".to_string()))?;
+
+    let principal = auth.verify(token)?;
+    req.extensions_mut().insert(principal);
+
+    Ok(next.run(req).await)
+}
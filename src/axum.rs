@@ -1,61 +1,19 @@
-use axum::{
-    extract::{Path, State},
-    http::StatusCode,
-    response::Json,
-    routing::{get, post, put, delete},
-    Router,
+use axum::{middleware, response::Json, routing::get, Router};
+use clap::Parser;
+use hello_rust::auth::{require_auth, JwtAuth, Principal};
+use hello_rust::config::{Config, ConfigArgs};
+use hello_rust::health::Health;
+use hello_rust::limits::Limits;
+use hello_rust::metrics::Metrics;
+use hello_rust::shutdown::{self, Shutdown};
+use hello_rust::telemetry;
+use hello_rust::timeout::Timeouts;
+use hello_rust::tls::{self, TlsListener};
+use hello_rust::user::{
+    admin_router, docs_router, user_router, AppState, AuditContext, CreateUserRequest,
+    InMemoryUserStore, UserStore,
 };
-use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
-use std::sync::Arc;
-use tokio::sync::RwLock;
-
-// 数据模型
-#[derive(Debug, Serialize, Deserialize, Clone)]
-struct User {
-    id: u32,
-    name: String,
-    email: String,
-}
-
-// 应用状态
-#[derive(Clone)]
-struct AppState {
-    users: Arc<RwLock<HashMap<u32, User>>>,
-}
-
-impl AppState {
-    fn new() -> Self {
-        let mut users = HashMap::new();
-        users.insert(1, User {
-            id: 1,
-            name: String::from("Alice"),
-            email: String::from("alice@example.com"),
-        });
-        users.insert(2, User {
-            id: 2,
-            name: String::from("Bob"),
-            email: String::from("bob@example.com"),
-        });
-        
-        AppState {
-            users: Arc::new(RwLock::new(users)),
-        }
-    }
-}
-
-// 请求和响应类型
-#[derive(Debug, Deserialize)]
-struct CreateUserRequest {
-    name: String,
-    email: String,
-}
-
-#[derive(Debug, Deserialize)]
-struct UpdateUserRequest {
-    name: Option<String>,
-    email: Option<String>,
-}
+use hello_rust::web::WebLayers;
 
 // 路由处理函数
 async fn root() -> Json<serde_json::Value> {
@@ -64,79 +22,6 @@ async fn root() -> Json<serde_json::Value> {
     }))
 }
 
-async fn get_users(State(state): State<AppState>) -> Json<Vec<User>> {
-    let users = state.users.read().await;
-    let user_list: Vec<User> = users.values().cloned().collect();
-    Json(user_list)
-}
-
-async fn get_user(
-    State(state): State<AppState>,
-    Path(id): Path<u32>,
-) -> Result<Json<User>, StatusCode> {
-    let users = state.users.read().await;
-    
-    if let Some(user) = users.get(&id) {
-        Ok(Json(user.clone()))
-    } else {
-        Err(StatusCode::NOT_FOUND)
-    }
-}
-
-async fn create_user(
-    State(state): State<AppState>,
-    Json(payload): Json<CreateUserRequest>,
-) -> Result<Json<User>, StatusCode> {
-    if payload.name.is_empty() || payload.email.is_empty() {
-        return Err(StatusCode::BAD_REQUEST);
-    }
-    
-    let mut users = state.users.write().await;
-    let new_id = users.keys().max().unwrap_or(&0) + 1;
-    
-    let new_user = User {
-        id: new_id,
-        name: payload.name,
-        email: payload.email,
-    };
-    
-    users.insert(new_id, new_user.clone());
-    Ok(Json(new_user))
-}
-
-async fn update_user(
-    State(state): State<AppState>,
-    Path(id): Path<u32>,
-    Json(payload): Json<UpdateUserRequest>,
-) -> Result<Json<User>, StatusCode> {
-    let mut users = state.users.write().await;
-    
-    if let Some(user) = users.get_mut(&id) {
-        if let Some(name) = payload.name {
-            user.name = name;
-        }
-        if let Some(email) = payload.email {
-            user.email = email;
-        }
-        Ok(Json(user.clone()))
-    } else {
-        Err(StatusCode::NOT_FOUND)
-    }
-}
-
-async fn delete_user(
-    State(state): State<AppState>,
-    Path(id): Path<u32>,
-) -> StatusCode {
-    let mut users = state.users.write().await;
-    
-    if users.remove(&id).is_some() {
-        StatusCode::NO_CONTENT
-    } else {
-        StatusCode::NOT_FOUND
-    }
-}
-
 // 异步数据路由
 async fn async_data() -> Json<serde_json::Value> {
     // 模拟异步操作
@@ -148,53 +33,142 @@ async fn async_data() -> Json<serde_json::Value> {
     }))
 }
 
-// 认证中间件
-// async fn auth_middleware(
-//     headers: axum::http::HeaderMap,
-// ) -> Result<(), StatusCode> {
-//     if let Some(auth_header) = headers.get("authorization") {
-//         if auth_header.to_str().unwrap_or("").starts_with("Bearer ") {
-//             return Ok(());
-//         }
-//     }
-//     Err(StatusCode::UNAUTHORIZED)
-// }
-
-async fn protected_route() -> Json<serde_json::Value> {
+// 受保护的路由，调用方信息由认证中间件注入
+async fn protected_route(principal: Principal) -> Json<serde_json::Value> {
     Json(serde_json::json!({
         "message": "受保护的路由",
         "user": {
-            "id": 1,
-            "name": "Authenticated User"
+            "id": principal.subject,
+            "name": principal.name
         }
     }))
 }
 
+// 初始数据
+async fn seed_users(store: &InMemoryUserStore) {
+    for (name, email) in [("Alice", "alice@example.com"), ("Bob", "bob@example.com")] {
+        let request = CreateUserRequest {
+            name: name.to_string(),
+            email: email.to_string(),
+        };
+        store.create_user(&request, &AuditContext::system("seed")).await.unwrap();
+    }
+}
+
 // 创建路由
-fn create_router() -> Router {
-    let state = AppState::new();
+async fn create_router(
+    config: &Config,
+    auth: Option<JwtAuth>,
+    health: Health,
+    shutdown: &Shutdown,
+) -> Router {
+    let store = InMemoryUserStore::new();
+    seed_users(&store).await;
     
-    Router::new()
+    // 指标：抓取时刷新当前用户数
+    let metrics = {
+        let store = store.clone();
+        Metrics::install().with_collector(move || {
+            let store = store.clone();
+            Box::pin(async move {
+                metrics::gauge!("app_users").set(store.count().await as f64);
+            })
+        })
+    };
+    
+    let state = AppState::new(store)
+        .require_if_match(config.api.require_if_match)
+        .purge_retention(chrono::Duration::days(config.api.purge_retention_days.into()))
+        .event_replay_capacity(config.api.event_replay_capacity);
+    state.events.close_on(shutdown);
+    // 限流等请求限制需先于认证中间件挂载
+    let limits = Limits::new(&config.limits);
+    let mut users = limits.apply(user_router(state.clone()));
+    let mut app = Router::new()
         .route("/", get(root))
-        .route("/api/users", get(get_users))
-        .route("/api/users", post(create_user))
-        .route("/api/users/{id}", get(get_user))
-        .route("/api/users/{id}", put(update_user))
-        .route("/api/users/{id}", delete(delete_user))
-        .route("/api/async-data", get(async_data))
-        .route("/api/protected", get(protected_route))
-        .with_state(state)
+        .route("/api/async-data", get(async_data));
+    
+    // 可选：用户接口需要 Bearer 令牌；受保护路由和管理接口始终需要，未配置密钥时不提供
+    match auth {
+        Some(auth) => {
+            let auth_layer = middleware::from_fn_with_state(auth, require_auth);
+            if config.auth.protect_user_routes {
+                users = users.route_layer(auth_layer.clone());
+            }
+            let admin = limits
+                .apply(admin_router(state))
+                .route_layer(auth_layer.clone());
+            app = app
+                .route("/api/protected", get(protected_route).route_layer(auth_layer))
+                .merge(admin);
+        }
+        None => tracing::warn!("auth.jwt_secret 未配置，受保护路由和管理接口不可用"),
+    }
+    let app = app.merge(users);
+    // API 文档不受超时限制
+    let app = Timeouts::new(&config.timeouts).apply(app).merge(docs_router());
+    
+    metrics.instrument(health.mount(app))
+}
+
+// 命令行参数
+#[derive(Parser)]
+struct Cli {
+    #[command(flatten)]
+    config: ConfigArgs,
 }
 
 #[tokio::main]
-async fn main() {
-    let app = create_router();
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let cli = Cli::parse();
+    let config = match Config::load(&cli.config) {
+        Ok(config) => config,
+        Err(err) => {
+            eprintln!("{}", err);
+            std::process::exit(1);
+        }
+    };
+    if cli.config.print_config {
+        print!("{}", config.to_redacted_toml());
+        return Ok(());
+    }
+    telemetry::init(&config.log.level, config.log.format);
+    
+    // 认证配置，未配置密钥时只提供公开接口
+    let auth = config
+        .auth
+        .jwt_secret
+        .as_ref()
+        .map(|secret| JwtAuth::new(secret.as_bytes()));
     
-    println!("服务器运行在 http://localhost:3000");
+    // 用户事件只在 Postgres 存储中写入 outbox
+    if !config.webhooks.endpoints.is_empty() {
+        tracing::warn!("内存存储不投递 webhook，webhooks.endpoints 将被忽略");
+    }
+    
+    let shutdown = Shutdown::new();
+    shutdown.listen_for_signals();
+    
+    // 内存存储没有外部依赖，就绪检查只反映是否正在关闭
+    let health = Health::new(shutdown.clone(), config.server.readiness_timeout());
+    
+    let app = create_router(&config, auth, health, &shutdown).await;
+    let app = WebLayers::new(&config.http).apply(app);
+    let app = telemetry::with_request_tracing(app);
+    
+    // 配置了证书时以 HTTPS 提供服务，证书文件变化后自动重新加载
+    let listener = tokio::net::TcpListener::bind(config.server.bind).await?;
+    let addr = listener.local_addr()?;
+    if config.tls.is_enabled() {
+        let listener = TlsListener::new(listener, &config.tls)?;
+        tracing::info!("服务器运行在 https://{}", addr);
+        tls::serve(listener, app, shutdown, config.server.shutdown_timeout()).await?;
+    } else {
+        tracing::info!("服务器运行在 http://{}", addr);
+        shutdown::serve(listener, app, shutdown, config.server.shutdown_timeout()).await?;
+    }
     
-    let listener = tokio::net::TcpListener::bind("127.0.0.1:3000")
-        .await
-        .unwrap();
+    tracing::info!("服务器已关闭");
     
-    axum::serve(listener, app).await.unwrap();
+    Ok(())
 }
\ No newline at end of file
@@ -0,0 +1,278 @@
+use std::{borrow::Cow, error::Error, fmt};
+
+use axum::{
+    extract::rejection::{JsonRejection, PathRejection, QueryRejection},
+    http::{header, HeaderValue, StatusCode},
+    response::{IntoResponse, Json, Response},
+};
+use serde::Serialize;
+use utoipa::{
+    openapi::{ArrayBuilder, ObjectBuilder, RefOr, Schema, Type},
+    PartialSchema, ToSchema,
+};
+use validator::ValidationErrors;
+
+use crate::user::StoreError;
+
+// 字段级错误
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct FieldError {
+    pub field: String,
+    #[schema(value_type = String)]
+    pub code: Cow<'static, str>,
+    pub message: String,
+}
+
+impl FieldError {
+    pub fn new(
+        field: impl Into<String>,
+        code: impl Into<Cow<'static, str>>,
+        message: impl Into<String>,
+    ) -> Self {
+        Self {
+            field: field.into(),
+            code: code.into(),
+            message: message.into(),
+        }
+    }
+}
+
+// 接口错误，统一序列化为 {"code", "message", "fields"}
+#[derive(Debug)]
+pub enum ApiError {
+    Rejected { status: StatusCode, message: String },
+    // 请求未携带凭证
+    Unauthorized(String),
+    // 凭证无效或已过期
+    InvalidToken(String),
+    // 已认证但无权访问
+    Forbidden(String),
+    NotFound(String),
+    Conflict { message: String, fields: Vec<FieldError> },
+    PreconditionFailed(String),
+    PreconditionRequired(String),
+    Validation(Vec<FieldError>),
+    // 超出限流配额，retry_after 为建议的等待秒数
+    TooManyRequests { message: String, retry_after: u64 },
+    // 服务过载，暂时无法处理
+    Unavailable(String),
+    // 处理超时
+    Timeout(String),
+    Internal(String),
+}
+
+impl ApiError {
+    pub fn status(&self) -> StatusCode {
+        match self {
+            ApiError::Rejected { status, .. } => *status,
+            ApiError::Unauthorized(_) | ApiError::InvalidToken(_) => StatusCode::UNAUTHORIZED,
+            ApiError::Forbidden(_) => StatusCode::FORBIDDEN,
+            ApiError::NotFound(_) => StatusCode::NOT_FOUND,
+            ApiError::Conflict { .. } => StatusCode::CONFLICT,
+            ApiError::PreconditionFailed(_) => StatusCode::PRECONDITION_FAILED,
+            ApiError::PreconditionRequired(_) => StatusCode::PRECONDITION_REQUIRED,
+            ApiError::Validation(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            ApiError::TooManyRequests { .. } => StatusCode::TOO_MANY_REQUESTS,
+            ApiError::Unavailable(_) => StatusCode::SERVICE_UNAVAILABLE,
+            ApiError::Timeout(_) => StatusCode::GATEWAY_TIMEOUT,
+            ApiError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    pub fn code(&self) -> &'static str {
+        match self {
+            ApiError::Rejected { .. } => "invalid_request",
+            ApiError::Unauthorized(_) => "unauthorized",
+            ApiError::InvalidToken(_) => "invalid_token",
+            ApiError::Forbidden(_) => "forbidden",
+            ApiError::NotFound(_) => "not_found",
+            ApiError::Conflict { .. } => "conflict",
+            ApiError::PreconditionFailed(_) => "precondition_failed",
+            ApiError::PreconditionRequired(_) => "precondition_required",
+            ApiError::Validation(_) => "validation_failed",
+            ApiError::TooManyRequests { .. } => "rate_limited",
+            ApiError::Unavailable(_) => "unavailable",
+            ApiError::Timeout(_) => "timeout",
+            ApiError::Internal(_) => "internal_error",
+        }
+    }
+
+    // RFC 6750：未携带凭证时只返回 realm，凭证无效时附带 error
+    fn challenge(&self) -> Option<String> {
+        match self {
+            ApiError::Unauthorized(_) => Some(r#"Bearer realm="hello_rust""#.to_string()),
+            ApiError::InvalidToken(msg) => Some(format!(
+                r#"Bearer realm="hello_rust", error="invalid_token", error_description="{}""#,
+                msg
+            )),
+            _ => None,
+        }
+    }
+
+    fn fields(&self) -> &[FieldError] {
+        match self {
+            ApiError::Conflict { fields, .. } | ApiError::Validation(fields) => fields,
+            _ => &[],
+        }
+    }
+}
+
+impl fmt::Display for ApiError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ApiError::Rejected { message: msg, .. }
+            | ApiError::Unauthorized(msg)
+            | ApiError::InvalidToken(msg)
+            | ApiError::Forbidden(msg)
+            | ApiError::NotFound(msg)
+            | ApiError::Conflict { message: msg, .. }
+            | ApiError::PreconditionFailed(msg)
+            | ApiError::PreconditionRequired(msg)
+            | ApiError::TooManyRequests { message: msg, .. }
+            | ApiError::Unavailable(msg)
+            | ApiError::Timeout(msg) => write!(f, "{}", msg),
+            ApiError::Validation(fields) => {
+                write!(f, "Validation failed for {} field(s)", fields.len())
+            }
+            // 内部错误细节不返回给客户端
+            ApiError::Internal(_) => write!(f, "Internal server error"),
+        }
+    }
+}
+
+impl Error for ApiError {}
+
+#[derive(Serialize)]
+struct ErrorBody<'a> {
+    code: &'static str,
+    message: String,
+    #[serde(skip_serializing_if = "<[FieldError]>::is_empty")]
+    fields: &'a [FieldError],
+}
+
+// 与响应体格式相同，用于在批量操作的结果中逐条报告错误
+impl Serialize for ApiError {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        ErrorBody {
+            code: self.code(),
+            message: self.to_string(),
+            fields: self.fields(),
+        }
+        .serialize(serializer)
+    }
+}
+
+// 文档中的错误响应体，与 ErrorBody 的序列化结果一致
+impl PartialSchema for ApiError {
+    fn schema() -> RefOr<Schema> {
+        ObjectBuilder::new()
+            .property("code", ObjectBuilder::new().schema_type(Type::String))
+            .required("code")
+            .property("message", ObjectBuilder::new().schema_type(Type::String))
+            .required("message")
+            .property("fields", ArrayBuilder::new().items(FieldError::schema()))
+            .into()
+    }
+}
+
+impl ToSchema for ApiError {
+    fn name() -> Cow<'static, str> {
+        Cow::Borrowed("Error")
+    }
+
+    fn schemas(schemas: &mut Vec<(String, RefOr<Schema>)>) {
+        schemas.push((FieldError::name().into(), FieldError::schema()));
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        if let ApiError::Internal(detail) = &self {
+            tracing::error!(error = %detail, "internal error");
+        }
+
+        let mut response = (self.status(), Json(&self)).into_response();
+        if let Some(challenge) = self.challenge() {
+            if let Ok(value) = HeaderValue::from_str(&challenge) {
+                response.headers_mut().insert(header::WWW_AUTHENTICATE, value);
+            }
+        }
+        if let ApiError::TooManyRequests { retry_after, .. } = &self {
+            response
+                .headers_mut()
+                .insert(header::RETRY_AFTER, HeaderValue::from(*retry_after));
+        }
+        response
+    }
+}
+
+impl From<StoreError> for ApiError {
+    fn from(err: StoreError) -> Self {
+        if err.is_timeout() {
+            tracing::warn!(error = %err, "database operation timed out");
+            return ApiError::Timeout("The database did not respond in time".to_string());
+        }
+        match err {
+            StoreError::Conflict(field) => ApiError::Conflict {
+                message: format!("A user with this {} already exists", field),
+                fields: vec![FieldError::new(field, "taken", "already in use")],
+            },
+            StoreError::VersionMismatch => ApiError::PreconditionFailed(
+                "The user has been modified since it was last read".to_string(),
+            ),
+            StoreError::Database(err) => ApiError::Internal(err.to_string()),
+        }
+    }
+}
+
+impl From<JsonRejection> for ApiError {
+    fn from(rejection: JsonRejection) -> Self {
+        ApiError::Rejected {
+            status: rejection.status(),
+            message: rejection.body_text(),
+        }
+    }
+}
+
+impl From<PathRejection> for ApiError {
+    fn from(rejection: PathRejection) -> Self {
+        ApiError::Rejected {
+            status: rejection.status(),
+            message: rejection.body_text(),
+        }
+    }
+}
+
+impl From<QueryRejection> for ApiError {
+    fn from(rejection: QueryRejection) -> Self {
+        ApiError::Rejected {
+            status: rejection.status(),
+            message: rejection.body_text(),
+        }
+    }
+}
+
+// 将 validator 的校验结果展开为按字段名排序的 FieldError 列表
+pub(crate) fn field_errors(errors: &ValidationErrors) -> Vec<FieldError> {
+    let mut fields: Vec<FieldError> = errors
+        .field_errors()
+        .into_iter()
+        .flat_map(|(field, errors)| {
+            errors.iter().map(move |err| {
+                let message = match &err.message {
+                    Some(message) => message.to_string(),
+                    None => format!("failed {} validation", err.code),
+                };
+                FieldError::new(field.to_string(), err.code.clone(), message)
+            })
+        })
+        .collect();
+    fields.sort_by(|a, b| a.field.cmp(&b.field));
+    fields
+}
+
+impl From<ValidationErrors> for ApiError {
+    fn from(errors: ValidationErrors) -> Self {
+        ApiError::Validation(field_errors(&errors))
+    }
+}
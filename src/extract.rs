@@ -0,0 +1,164 @@
+use axum::{
+    body::Bytes,
+    extract::{FromRequest, FromRequestParts, Request},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+};
+use serde::de::{self, DeserializeOwned, Deserializer, Visitor};
+use serde::forward_to_deserialize_any;
+use serde_json::{Map, Value};
+use validator::Validate;
+
+use crate::error::{field_errors, ApiError, FieldError};
+
+// 与 axum::Json 相同，但解析失败时返回 ApiError 格式的响应体
+#[derive(FromRequest)]
+#[from_request(via(axum::Json), rejection(ApiError))]
+pub struct Json<T>(pub T);
+
+impl<T: serde::Serialize> IntoResponse for Json<T> {
+    fn into_response(self) -> Response {
+        axum::Json(self.0).into_response()
+    }
+}
+
+// 与 axum::extract::Path 相同，但解析失败时返回 ApiError
+#[derive(FromRequestParts)]
+#[from_request(via(axum::extract::Path), rejection(ApiError))]
+pub struct Path<T>(pub T);
+
+// 与 axum::extract::Query 相同，但解析失败时返回 ApiError
+#[derive(FromRequestParts)]
+#[from_request(via(axum::extract::Query), rejection(ApiError))]
+pub struct Query<T>(pub T);
+
+// 读取请求体及其媒体类型（小写、不含参数），供按 Content-Type 选择解析方式的提取器使用
+pub async fn typed_body<S: Send + Sync>(
+    req: Request,
+    state: &S,
+) -> Result<(String, Bytes), ApiError> {
+    let content_type = req
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.split(';').next())
+        .map(|value| value.trim().to_ascii_lowercase())
+        .unwrap_or_default();
+
+    let body = Bytes::from_request(req, state)
+        .await
+        .map_err(|rejection| ApiError::Rejected {
+            status: rejection.status(),
+            message: rejection.body_text(),
+        })?;
+    Ok((content_type, body))
+}
+
+// 解析 JSON 后执行 Validate 校验，未知字段和所有校验失败的字段一并返回 422
+pub struct ValidJson<T>(pub T);
+
+impl<S, T> FromRequest<S> for ValidJson<T>
+where
+    S: Send + Sync,
+    T: DeserializeOwned + Validate,
+{
+    type Rejection = ApiError;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let Json(value) = Json::<serde_json::Value>::from_request(req, state).await?;
+        from_value(value).map(ValidJson)
+    }
+}
+
+// 派生的 Deserialize 会把结构体接受的字段名传给 deserialize_struct，
+// 借此取得字段列表，不必另外维护
+fn struct_fields<T: DeserializeOwned>() -> &'static [&'static str] {
+    struct FieldNames<'a>(&'a mut &'static [&'static str]);
+
+    impl<'de> Deserializer<'de> for FieldNames<'_> {
+        type Error = de::value::Error;
+
+        fn deserialize_any<V: Visitor<'de>>(self, _visitor: V) -> Result<V::Value, Self::Error> {
+            Err(de::Error::custom("expected a struct"))
+        }
+
+        fn deserialize_struct<V: Visitor<'de>>(
+            self,
+            _name: &'static str,
+            fields: &'static [&'static str],
+            _visitor: V,
+        ) -> Result<V::Value, Self::Error> {
+            *self.0 = fields;
+            Err(de::Error::custom("only the field names are read"))
+        }
+
+        forward_to_deserialize_any! {
+            bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+            bytes byte_buf option unit unit_struct newtype_struct seq tuple
+            tuple_struct map enum identifier ignored_any
+        }
+    }
+
+    let mut fields: &'static [&'static str] = &[];
+    let _ = T::deserialize(FieldNames(&mut fields));
+    fields
+}
+
+// 将 JSON 对象按 T 的 Deserialize 逐字段解析并校验，缺失、类型错误、未知和校验失败的字段
+// 一并以 422 返回。T 需带 #[serde(default)]：单独解析每个字段时其余字段取默认值，
+// 出错的字段以默认值参与校验
+pub fn from_value<T>(value: Value) -> Result<T, ApiError>
+where
+    T: DeserializeOwned + Validate,
+{
+    let Value::Object(mut object) = value else {
+        return Err(ApiError::Rejected {
+            status: StatusCode::UNPROCESSABLE_ENTITY,
+            message: "Expected a JSON object".to_string(),
+        });
+    };
+
+    let fields = struct_fields::<T>();
+    let mut errors: Vec<FieldError> = object
+        .keys()
+        .filter(|key| !fields.contains(&key.as_str()))
+        .map(|key| FieldError::new(key, "unknown_field", "is not an accepted field"))
+        .collect();
+    object.retain(|key, _| fields.contains(&key.as_str()));
+
+    // 缺失的字段按 null 处理，不接受 null 的字段即为必填
+    for field in fields {
+        let value = object.get(*field).cloned().unwrap_or(Value::Null);
+        let is_null = value.is_null();
+        let single = Map::from_iter([(field.to_string(), value)]);
+        if let Err(err) = serde_json::from_value::<T>(Value::Object(single)) {
+            errors.push(if is_null {
+                FieldError::new(*field, "required", "is required")
+            } else {
+                FieldError::new(*field, "type", err.to_string())
+            });
+            object.remove(*field);
+        }
+    }
+
+    let payload: T =
+        serde_json::from_value(Value::Object(object)).map_err(|err| ApiError::Rejected {
+            status: StatusCode::UNPROCESSABLE_ENTITY,
+            message: format!("Failed to deserialize the JSON body: {}", err),
+        })?;
+    // 已因缺失或类型错误报告的字段以默认值参与校验，不再重复报告
+    if let Err(validation) = payload.validate() {
+        let reported: Vec<String> = errors.iter().map(|err| err.field.clone()).collect();
+        errors.extend(
+            field_errors(&validation)
+                .into_iter()
+                .filter(|err| !reported.contains(&err.field)),
+        );
+    }
+    if !errors.is_empty() {
+        errors.sort_by(|a, b| a.field.cmp(&b.field));
+        return Err(ApiError::Validation(errors));
+    }
+
+    Ok(payload)
+}
@@ -0,0 +1,111 @@
+use std::{collections::BTreeMap, sync::Arc, time::Duration};
+
+use axum::{extract::State, http::StatusCode, response::Json, routing::get, Router};
+use futures::future::{join_all, BoxFuture};
+use serde::Serialize;
+use sqlx::PgPool;
+use tokio::time::Instant;
+
+use crate::shutdown::Shutdown;
+
+// 依赖检查，返回 Err 时附带失败原因
+pub type Check = Arc<dyn Fn() -> BoxFuture<'static, Result<(), String>> + Send + Sync>;
+
+#[derive(Clone)]
+pub struct Health {
+    shutdown: Shutdown,
+    timeout: Duration,
+    checks: Vec<(&'static str, Check)>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DependencyStatus {
+    pub status: &'static str,
+    pub latency_ms: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Readiness {
+    pub status: &'static str,
+    pub checks: BTreeMap<&'static str, DependencyStatus>,
+}
+
+impl Health {
+    // timeout 为单个依赖检查的最长耗时
+    pub fn new(shutdown: Shutdown, timeout: Duration) -> Self {
+        Self {
+            shutdown,
+            timeout,
+            checks: Vec::new(),
+        }
+    }
+
+    pub fn with_check<F>(mut self, name: &'static str, check: F) -> Self
+    where
+        F: Fn() -> BoxFuture<'static, Result<(), String>> + Send + Sync + 'static,
+    {
+        self.checks.push((name, Arc::new(check)));
+        self
+    }
+
+    pub async fn readiness(&self) -> Readiness {
+        let results = join_all(self.checks.iter().map(|(name, check)| async move {
+            let start = Instant::now();
+            let result = match tokio::time::timeout(self.timeout, check()).await {
+                Ok(result) => result,
+                Err(_) => Err(format!("timed out after {:?}", self.timeout)),
+            };
+            let status = DependencyStatus {
+                status: if result.is_ok() { "up" } else { "down" },
+                latency_ms: start.elapsed().as_millis() as u64,
+                error: result.err(),
+            };
+            (*name, status)
+        }))
+        .await;
+
+        let checks: BTreeMap<_, _> = results.into_iter().collect();
+        let status = if self.shutdown.is_draining() {
+            "draining"
+        } else if checks.values().all(|check| check.error.is_none()) {
+            "ready"
+        } else {
+            "not_ready"
+        };
+
+        Readiness { status, checks }
+    }
+
+    // 挂载 GET /healthz 和 GET /readyz
+    pub fn mount(&self, router: Router) -> Router {
+        router
+            .route("/healthz", get(liveness))
+            .route("/readyz", get(readiness).with_state(self.clone()))
+    }
+}
+
+// 进程存活即返回 200
+async fn liveness() -> Json<serde_json::Value> {
+    Json(serde_json::json!({ "status": "alive" }))
+}
+
+async fn readiness(State(health): State<Health>) -> (StatusCode, Json<Readiness>) {
+    let readiness = health.readiness().await;
+    let code = if readiness.status == "ready" {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    (code, Json(readiness))
+}
+
+// 数据库检查：SELECT 1
+pub async fn ping_database(pool: PgPool) -> Result<(), String> {
+    sqlx::query("SELECT 1")
+        .execute(&pool)
+        .await
+        .map(|_| ())
+        .map_err(|err| err.to_string())
+}
@@ -1 +1,16 @@
-pub mod calculator;
\ No newline at end of file
+pub mod auth;
+pub mod calculator;
+pub mod config;
+pub mod error;
+pub mod extract;
+pub mod health;
+pub mod limits;
+pub mod metrics;
+pub mod migrate;
+pub mod shutdown;
+pub mod telemetry;
+pub mod timeout;
+pub mod tls;
+pub mod user;
+pub mod web;
+pub mod webhook;
\ No newline at end of file
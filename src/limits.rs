@@ -0,0 +1,318 @@
+use std::{
+    collections::HashMap,
+    net::{IpAddr, SocketAddr},
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use axum::{
+    extract::{ConnectInfo, DefaultBodyLimit, MatchedPath, Request, State},
+    http::{HeaderMap, HeaderValue},
+    middleware::{self, Next},
+    response::{IntoResponse, Response},
+    Router,
+};
+use tokio::sync::Semaphore;
+
+use crate::auth::Principal;
+use crate::config::{LimitsConfig, Quota};
+use crate::error::ApiError;
+use crate::metrics::request_labels;
+
+// 用户和管理接口共用的请求限制
+#[derive(Clone)]
+pub struct Limits {
+    rate: RateLimiter,
+    concurrency: ConcurrencyLimit,
+    max_body_bytes: usize,
+}
+
+impl Limits {
+    pub fn new(config: &LimitsConfig) -> Self {
+        Limits {
+            rate: RateLimiter::new(config),
+            concurrency: ConcurrencyLimit::new(config.max_concurrent_requests),
+            max_body_bytes: config.max_body_bytes,
+        }
+    }
+
+    // 挂载请求体上限、限流和并发上限；认证中间件需在此之后以 route_layer 挂载，
+    // 限流才能读取到 Principal，被限流的请求也不会占用并发许可
+    pub fn apply(&self, router: Router) -> Router {
+        router
+            .route_layer(middleware::from_fn_with_state(
+                self.concurrency.clone(),
+                limit_concurrency,
+            ))
+            .route_layer(middleware::from_fn_with_state(
+                self.rate.clone(),
+                rate_limit,
+            ))
+            .route_layer(DefaultBodyLimit::max(self.max_body_bytes))
+    }
+}
+
+// 清理已回满（即空闲）令牌桶的间隔
+const SWEEP_INTERVAL: Duration = Duration::from_secs(60);
+
+// 按客户端和路由限流的令牌桶，已认证的请求按 Principal 计数，其余按客户端 IP。
+// 需要作为 route_layer 挂载在认证中间件之内，才能读取到 MatchedPath 和 Principal
+#[derive(Clone)]
+pub struct RateLimiter {
+    inner: Arc<Mutex<Buckets>>,
+    default_quota: Quota,
+    routes: Arc<HashMap<String, Quota>>,
+    trust_forwarded_for: bool,
+}
+
+struct Buckets {
+    buckets: HashMap<(String, String), Bucket>,
+    last_sweep: Instant,
+}
+
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+// 一次取令牌的结果，用于生成 RateLimit-* 响应头
+struct Decision {
+    allowed: bool,
+    limit: u32,
+    remaining: u32,
+    // 令牌桶回满所需的秒数
+    reset: u64,
+    // 被拒绝时下一个令牌可用前的秒数
+    retry_after: u64,
+}
+
+impl Bucket {
+    fn full(quota: Quota, now: Instant) -> Self {
+        Bucket {
+            tokens: quota.burst.into(),
+            updated: now,
+        }
+    }
+
+    fn refill(&mut self, quota: Quota, now: Instant) {
+        let elapsed = now.duration_since(self.updated).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * quota.per_second).min(quota.burst.into());
+        self.updated = now;
+    }
+
+    fn take(&mut self, quota: Quota, now: Instant) -> Decision {
+        self.refill(quota, now);
+        let allowed = self.tokens >= 1.0;
+        if allowed {
+            self.tokens -= 1.0;
+        }
+        let missing = f64::from(quota.burst) - self.tokens;
+        Decision {
+            allowed,
+            limit: quota.burst,
+            remaining: self.tokens.floor() as u32,
+            reset: (missing / quota.per_second).ceil() as u64,
+            retry_after: ((1.0 - self.tokens).max(0.0) / quota.per_second).ceil() as u64,
+        }
+    }
+}
+
+impl RateLimiter {
+    pub fn new(config: &LimitsConfig) -> Self {
+        let routes = config
+            .routes
+            .iter()
+            .map(|route| (route.route.clone(), route.quota()))
+            .collect();
+        RateLimiter {
+            inner: Arc::new(Mutex::new(Buckets {
+                buckets: HashMap::new(),
+                last_sweep: Instant::now(),
+            })),
+            default_quota: config.default_quota,
+            routes: Arc::new(routes),
+            trust_forwarded_for: config.trust_forwarded_for,
+        }
+    }
+
+    // 单独配置了配额的路由各用一个桶，其余路由共用默认桶
+    fn check(&self, route: &str, client: String) -> Decision {
+        let (scope, quota) = match self.routes.get(route) {
+            Some(quota) => (route.to_string(), *quota),
+            None => ("*".to_string(), self.default_quota),
+        };
+
+        let now = Instant::now();
+        let mut inner = self.inner.lock().unwrap();
+        if now.duration_since(inner.last_sweep) >= SWEEP_INTERVAL {
+            inner.sweep(&self.routes, self.default_quota, now);
+        }
+        inner
+            .buckets
+            .entry((scope, client))
+            .or_insert_with(|| Bucket::full(quota, now))
+            .take(quota, now)
+    }
+
+    fn client_key(&self, req: &Request) -> String {
+        if let Some(principal) = req.extensions().get::<Principal>() {
+            return format!("principal:{}", principal.subject);
+        }
+        let forwarded = self
+            .trust_forwarded_for
+            .then(|| forwarded_for(req.headers()))
+            .flatten();
+        let peer = req
+            .extensions()
+            .get::<ConnectInfo<SocketAddr>>()
+            .map(|ConnectInfo(addr)| addr.ip());
+        match forwarded.or(peer) {
+            Some(ip) => format!("ip:{}", ip),
+            None => "ip:unknown".to_string(),
+        }
+    }
+}
+
+impl Buckets {
+    // 回满的桶与新建的桶等价，可以直接丢弃
+    fn sweep(&mut self, routes: &HashMap<String, Quota>, default_quota: Quota, now: Instant) {
+        self.buckets.retain(|(scope, _), bucket| {
+            let quota = routes.get(scope).copied().unwrap_or(default_quota);
+            bucket.refill(quota, now);
+            bucket.tokens < f64::from(quota.burst)
+        });
+        self.last_sweep = now;
+    }
+}
+
+// 与配置中 routes 的 route 对应的标识，如 "POST /api/users"
+pub fn route_key(req: &Request) -> String {
+    format!(
+        "{} {}",
+        req.method(),
+        req.extensions()
+            .get::<MatchedPath>()
+            .map_or(req.uri().path(), MatchedPath::as_str)
+    )
+}
+
+fn forwarded_for(headers: &HeaderMap) -> Option<IpAddr> {
+    headers
+        .get("x-forwarded-for")?
+        .to_str()
+        .ok()?
+        .split(',')
+        .next()?
+        .trim()
+        .parse()
+        .ok()
+}
+
+// 限流中间件：超出配额时返回 429 和 Retry-After，所有响应都带 RateLimit-* 头
+pub async fn rate_limit(State(limiter): State<RateLimiter>, req: Request, next: Next) -> Response {
+    let route = route_key(&req);
+    let client = limiter.client_key(&req);
+    let decision = limiter.check(&route, client);
+
+    let mut response = if decision.allowed {
+        next.run(req).await
+    } else {
+        let (method, route) = request_labels(&req);
+        metrics::counter!("http_requests_rate_limited_total", "method" => method, "route" => route)
+            .increment(1);
+        ApiError::TooManyRequests {
+            message: "Too many requests, please retry later".to_string(),
+            retry_after: decision.retry_after,
+        }
+        .into_response()
+    };
+
+    let headers = response.headers_mut();
+    headers.insert("ratelimit-limit", HeaderValue::from(decision.limit));
+    headers.insert("ratelimit-remaining", HeaderValue::from(decision.remaining));
+    headers.insert("ratelimit-reset", HeaderValue::from(decision.reset));
+    response
+}
+
+// 并发上限：许可用尽时立即返回 503，而不是排队等待。
+// 许可在处理函数返回响应时释放，流式响应体的传输不占用许可
+#[derive(Clone)]
+pub struct ConcurrencyLimit {
+    permits: Arc<Semaphore>,
+}
+
+impl ConcurrencyLimit {
+    pub fn new(max_concurrent_requests: usize) -> Self {
+        ConcurrencyLimit {
+            permits: Arc::new(Semaphore::new(max_concurrent_requests)),
+        }
+    }
+}
+
+pub async fn limit_concurrency(
+    State(limit): State<ConcurrencyLimit>,
+    req: Request,
+    next: Next,
+) -> Result<Response, ApiError> {
+    let Ok(_permit) = limit.permits.clone().try_acquire_owned() else {
+        let (method, route) = request_labels(&req);
+        metrics::counter!("http_requests_shed_total", "method" => method, "route" => route)
+            .increment(1);
+        return Err(ApiError::Unavailable(
+            "Server is busy, please retry later".to_string(),
+        ));
+    };
+    Ok(next.run(req).await)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn taken(decision: &Decision) -> (bool, u32, u64, u64) {
+        (
+            decision.allowed,
+            decision.remaining,
+            decision.reset,
+            decision.retry_after,
+        )
+    }
+
+    #[test]
+    fn bucket_refills_at_the_quota_rate_up_to_the_burst() {
+        let quota = Quota {
+            burst: 2,
+            per_second: 1.0,
+        };
+        let start = Instant::now();
+        let at = |millis| start + Duration::from_millis(millis);
+        let mut bucket = Bucket::full(quota, start);
+
+        // (allowed, remaining, reset, retry_after)
+        assert_eq!(taken(&bucket.take(quota, at(0))), (true, 1, 1, 0));
+        // 令牌取完后，下一个令牌要等 1 秒
+        assert_eq!(taken(&bucket.take(quota, at(0))), (true, 0, 2, 1));
+        assert_eq!(taken(&bucket.take(quota, at(0))), (false, 0, 2, 1));
+        // 0.5 个令牌不够一次请求
+        assert_eq!(taken(&bucket.take(quota, at(500))), (false, 0, 2, 1));
+        // 1.1 个令牌，取走一个后剩 0.1
+        assert_eq!(taken(&bucket.take(quota, at(1100))), (true, 0, 2, 1));
+        // 空闲再久也只积攒 burst 个
+        assert_eq!(taken(&bucket.take(quota, at(60_000))), (true, 1, 1, 0));
+    }
+
+    #[test]
+    fn slow_quotas_round_waits_up() {
+        let quota = Quota {
+            burst: 1,
+            per_second: 0.4,
+        };
+        let now = Instant::now();
+        let mut bucket = Bucket::full(quota, now);
+        // 2.5 秒补充一个令牌，向上取整为 3 秒
+        assert_eq!(taken(&bucket.take(quota, now)), (true, 0, 3, 3));
+        assert_eq!(taken(&bucket.take(quota, now)), (false, 0, 3, 3));
+        let later = now + Duration::from_secs(2);
+        assert_eq!(taken(&bucket.take(quota, later)), (false, 0, 1, 1));
+    }
+}
@@ -0,0 +1,181 @@
+use std::{future::Future, sync::Arc, time::Instant};
+
+use ::metrics::{counter, gauge, histogram};
+use axum::{
+    extract::{MatchedPath, Request, State},
+    http::header,
+    middleware::{self, Next},
+    response::{IntoResponse, Response},
+    routing::get,
+    Router,
+};
+use futures::future::BoxFuture;
+use metrics_exporter_prometheus::{Matcher, PrometheusBuilder, PrometheusHandle};
+use sqlx::PgPool;
+
+const LATENCY_BUCKETS: &[f64] = &[
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+// 抓取前执行的采集函数，用于刷新连接池、用户数等瞬时指标
+pub type Collector = Arc<dyn Fn() -> BoxFuture<'static, ()> + Send + Sync>;
+
+#[derive(Clone)]
+pub struct Metrics {
+    handle: PrometheusHandle,
+    collectors: Vec<Collector>,
+}
+
+impl Metrics {
+    // 安装全局 Prometheus recorder，每个进程只能调用一次
+    pub fn install() -> Self {
+        let handle = PrometheusBuilder::new()
+            .set_buckets_for_metric(
+                Matcher::Full("http_request_duration_seconds".to_string()),
+                LATENCY_BUCKETS,
+            )
+            .expect("latency buckets are not empty")
+            .set_buckets_for_metric(
+                Matcher::Full("db_pool_acquire_wait_seconds".to_string()),
+                LATENCY_BUCKETS,
+            )
+            .expect("latency buckets are not empty")
+            .install_recorder()
+            .expect("failed to install Prometheus recorder");
+
+        Self {
+            handle,
+            collectors: Vec::new(),
+        }
+    }
+
+    pub fn with_collector<F>(mut self, collector: F) -> Self
+    where
+        F: Fn() -> BoxFuture<'static, ()> + Send + Sync + 'static,
+    {
+        self.collectors.push(Arc::new(collector));
+        self
+    }
+
+    pub async fn render(&self) -> String {
+        for collector in &self.collectors {
+            collector().await;
+        }
+        self.handle.render()
+    }
+
+    // 挂载 GET /metrics，并为 router 中的所有路由统计请求数、耗时和处理中的请求数
+    pub fn instrument(&self, router: Router) -> Router {
+        router
+            .route("/metrics", get(metrics_handler).with_state(self.clone()))
+            .layer(middleware::from_fn(track_requests))
+    }
+}
+
+async fn metrics_handler(State(metrics): State<Metrics>) -> impl IntoResponse {
+    (
+        [(header::CONTENT_TYPE, "text/plain; version=0.0.4")],
+        metrics.render().await,
+    )
+}
+
+// 请求结束或被取消时都会减少处理中的请求数；客户端断开连接时处理函数的 future
+// 会在完成前被丢弃，此时 completed 仍为 false
+struct InFlightGuard {
+    method: String,
+    route: String,
+    completed: bool,
+}
+
+impl Drop for InFlightGuard {
+    fn drop(&mut self) {
+        gauge!(
+            "http_requests_in_flight",
+            "method" => self.method.clone(),
+            "route" => self.route.clone()
+        )
+        .decrement(1.0);
+        if !self.completed {
+            counter!(
+                "http_requests_cancelled_total",
+                "method" => self.method.clone(),
+                "route" => self.route.clone()
+            )
+            .increment(1);
+            tracing::debug!(route = %self.route, "request cancelled before completion");
+        }
+    }
+}
+
+// 请求方法和路由模板，各 HTTP 指标统一使用这两个标签
+pub fn request_labels(req: &Request) -> (String, String) {
+    let route = req
+        .extensions()
+        .get::<MatchedPath>()
+        .map_or("<unmatched>", MatchedPath::as_str)
+        .to_string();
+    (req.method().to_string(), route)
+}
+
+async fn track_requests(req: Request, next: Next) -> Response {
+    let (method, route) = request_labels(&req);
+
+    gauge!("http_requests_in_flight", "method" => method.clone(), "route" => route.clone())
+        .increment(1.0);
+    let mut guard = InFlightGuard {
+        method: method.clone(),
+        route: route.clone(),
+        completed: false,
+    };
+
+    let start = Instant::now();
+    let response = next.run(req).await;
+    guard.completed = true;
+    let latency = start.elapsed().as_secs_f64();
+
+    let labels = [
+        ("method", method),
+        ("route", route),
+        ("status", response.status().as_u16().to_string()),
+    ];
+    counter!("http_requests_total", &labels).increment(1);
+    histogram!("http_request_duration_seconds", &labels).record(latency);
+
+    response
+}
+
+// 连接池状态；等待连接的情况由 acquire 记录
+pub fn record_pool_stats(pool: &PgPool) {
+    let size = pool.size();
+    let idle = pool.num_idle() as u32;
+    gauge!("db_pool_connections", "state" => "idle").set(idle);
+    gauge!("db_pool_connections", "state" => "active").set(size.saturating_sub(idle));
+    gauge!("db_pool_max_connections").set(pool.options().get_max_connections());
+    // 确保 acquire 记录的指标在第一次借出连接前也存在
+    gauge!("db_pool_waiting").increment(0.0);
+    counter!("db_pool_acquire_timeouts_total").increment(0);
+}
+
+// 等待中的借出请求被取消时同样要减少等待数
+struct PoolWaitGuard;
+
+impl Drop for PoolWaitGuard {
+    fn drop(&mut self) {
+        gauge!("db_pool_waiting").decrement(1.0);
+    }
+}
+
+// 从连接池借出连接（或开启事务），记录正在等待的数量、等待时间和等待超时的次数
+pub async fn acquire<T>(
+    acquire: impl Future<Output = Result<T, sqlx::Error>>,
+) -> Result<T, sqlx::Error> {
+    gauge!("db_pool_waiting").increment(1.0);
+    let _guard = PoolWaitGuard;
+    let start = Instant::now();
+    let result = acquire.await;
+    histogram!("db_pool_acquire_wait_seconds").record(start.elapsed().as_secs_f64());
+    if let Err(sqlx::Error::PoolTimedOut) = &result {
+        counter!("db_pool_acquire_timeouts_total").increment(1);
+    }
+    result
+}
@@ -0,0 +1,88 @@
+use std::collections::HashMap;
+
+use sqlx::migrate::{Migrate, MigrateError, Migrator};
+use sqlx::PgPool;
+
+// 内嵌的迁移脚本，按版本号顺序执行，执行记录保存在 _sqlx_migrations 表中
+pub static MIGRATOR: Migrator = sqlx::migrate!("./migrations");
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MigrationState {
+    Applied,
+    Pending,
+    // 已执行的脚本与二进制中内嵌的内容不一致
+    ChecksumMismatch,
+    // 数据库中有记录，但二进制中没有对应脚本
+    Missing,
+}
+
+#[derive(Debug, Clone)]
+pub struct MigrationStatus {
+    pub version: i64,
+    pub description: String,
+    pub state: MigrationState,
+}
+
+// 执行所有未执行的迁移，已执行迁移的校验和不一致时报错
+pub async fn up(pool: &PgPool) -> Result<(), MigrateError> {
+    MIGRATOR.run(pool).await
+}
+
+// 回滚版本号大于 target 的所有迁移
+pub async fn down(pool: &PgPool, target: i64) -> Result<(), MigrateError> {
+    MIGRATOR.undo(pool, target).await
+}
+
+pub async fn status(pool: &PgPool) -> Result<Vec<MigrationStatus>, MigrateError> {
+    let mut conn = pool.acquire().await?;
+    conn.ensure_migrations_table().await?;
+    let mut applied: HashMap<i64, _> = conn
+        .list_applied_migrations()
+        .await?
+        .into_iter()
+        .map(|migration| (migration.version, migration))
+        .collect();
+
+    let mut statuses: Vec<MigrationStatus> = MIGRATOR
+        .iter()
+        .filter(|migration| migration.migration_type.is_up_migration())
+        .map(|migration| {
+            let state = match applied.remove(&migration.version) {
+                None => MigrationState::Pending,
+                Some(record) if record.checksum != migration.checksum => {
+                    MigrationState::ChecksumMismatch
+                }
+                Some(_) => MigrationState::Applied,
+            };
+            MigrationStatus {
+                version: migration.version,
+                description: migration.description.to_string(),
+                state,
+            }
+        })
+        .collect();
+
+    statuses.extend(applied.into_keys().map(|version| MigrationStatus {
+        version,
+        description: String::new(),
+        state: MigrationState::Missing,
+    }));
+    statuses.sort_by_key(|status| status.version);
+
+    Ok(statuses)
+}
+
+// 启动前校验：已执行的迁移必须与内嵌脚本完全一致
+pub async fn verify(pool: &PgPool) -> Result<Vec<MigrationStatus>, MigrateError> {
+    let statuses = status(pool).await?;
+    for status in &statuses {
+        match status.state {
+            MigrationState::ChecksumMismatch => {
+                return Err(MigrateError::VersionMismatch(status.version))
+            }
+            MigrationState::Missing => return Err(MigrateError::VersionMissing(status.version)),
+            MigrationState::Applied | MigrationState::Pending => {}
+        }
+    }
+    Ok(statuses)
+}
@@ -0,0 +1,116 @@
+use std::{
+    future::{Future, IntoFuture},
+    io,
+    net::SocketAddr,
+    sync::Arc,
+    time::Duration,
+};
+
+use axum::Router;
+use tokio::{net::TcpListener, sync::watch};
+
+// 关闭信号，触发后服务器停止接收新连接并进入排空阶段
+#[derive(Clone)]
+pub struct Shutdown {
+    tx: Arc<watch::Sender<bool>>,
+}
+
+impl Shutdown {
+    pub fn new() -> Self {
+        let (tx, _) = watch::channel(false);
+        Self { tx: Arc::new(tx) }
+    }
+
+    pub fn trigger(&self) {
+        self.tx.send_replace(true);
+    }
+
+    pub fn is_draining(&self) -> bool {
+        *self.tx.borrow()
+    }
+
+    pub async fn wait(&self) {
+        let mut rx = self.tx.subscribe();
+        // 发送端由自身持有，不会提前关闭
+        let _ = rx.wait_for(|draining| *draining).await;
+    }
+
+    // 不借用自身的 wait，用于 with_graceful_shutdown
+    pub fn signaled(&self) -> impl Future<Output = ()> + Send + 'static {
+        let shutdown = self.clone();
+        async move { shutdown.wait().await }
+    }
+
+    // 收到 SIGINT 或 SIGTERM 时触发关闭
+    pub fn listen_for_signals(&self) {
+        let shutdown = self.clone();
+        tokio::spawn(async move {
+            wait_for_signal().await;
+            tracing::info!("收到关闭信号，开始排空连接");
+            shutdown.trigger();
+        });
+    }
+}
+
+impl Default for Shutdown {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+async fn wait_for_signal() {
+    let ctrl_c = async {
+        tokio::signal::ctrl_c()
+            .await
+            .expect("failed to install Ctrl+C handler");
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+            .expect("failed to install SIGTERM handler")
+            .recv()
+            .await;
+    };
+
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {},
+        _ = terminate => {},
+    }
+}
+
+// 启动服务器；关闭信号触发后拒绝新连接，等待处理中的请求完成，
+// 超过 drain_timeout 仍未完成的连接将被直接断开
+pub async fn serve(
+    listener: TcpListener,
+    app: Router,
+    shutdown: Shutdown,
+    drain_timeout: Duration,
+) -> io::Result<()> {
+    // 携带对端地址，供限流按客户端 IP 计数
+    let app = app.into_make_service_with_connect_info::<SocketAddr>();
+    let server = axum::serve(listener, app).with_graceful_shutdown(shutdown.signaled());
+    drain(server, &shutdown, drain_timeout).await
+}
+
+// 运行已设置 with_graceful_shutdown 的服务器，关闭信号触发后最多再等待 drain_timeout
+pub async fn drain<F>(server: F, shutdown: &Shutdown, drain_timeout: Duration) -> io::Result<()>
+where
+    F: IntoFuture<Output = io::Result<()>>,
+{
+    let deadline = async {
+        shutdown.wait().await;
+        tokio::time::sleep(drain_timeout).await;
+    };
+
+    tokio::select! {
+        result = server.into_future() => result,
+        _ = deadline => {
+            tracing::warn!(?drain_timeout, "排空超时，强制关闭剩余连接");
+            Ok(())
+        }
+    }
+}
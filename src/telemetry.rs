@@ -0,0 +1,72 @@
+use std::time::Duration;
+
+use axum::{
+    extract::MatchedPath,
+    http::{Request, Response},
+    Router,
+};
+use serde::{Deserialize, Serialize};
+use tower_http::{
+    request_id::{MakeRequestUuid, PropagateRequestIdLayer, SetRequestIdLayer},
+    trace::TraceLayer,
+};
+use tracing::{field::Empty, Span};
+use tracing_subscriber::EnvFilter;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    Json,
+    Pretty,
+}
+
+// 初始化全局日志，level 使用 EnvFilter 语法，如 "info,sqlx=warn"
+pub fn init(level: &str, format: LogFormat) {
+    let filter = EnvFilter::try_new(level).unwrap_or_else(|_| EnvFilter::new("info"));
+    let builder = tracing_subscriber::fmt().with_env_filter(filter);
+    match format {
+        LogFormat::Json => builder
+            .json()
+            .flatten_event(true)
+            .with_current_span(true)
+            .with_span_list(false)
+            .init(),
+        LogFormat::Pretty => builder.init(),
+    }
+}
+
+// 为每个请求生成 X-Request-Id 并在响应中回传，
+// 同时记录方法、路由模板、状态码和耗时（不记录查询串和请求体，避免泄露个人信息）
+pub fn with_request_tracing(router: Router) -> Router {
+    let trace = TraceLayer::new_for_http()
+        .make_span_with(|req: &Request<_>| {
+            let route = req
+                .extensions()
+                .get::<MatchedPath>()
+                .map_or("<unmatched>", MatchedPath::as_str);
+            let request_id = req
+                .headers()
+                .get("x-request-id")
+                .and_then(|value| value.to_str().ok())
+                .unwrap_or_default();
+            tracing::info_span!(
+                "request",
+                request_id,
+                method = %req.method(),
+                route,
+                status = Empty,
+                latency_ms = Empty,
+            )
+        })
+        .on_request(())
+        .on_response(|res: &Response<_>, latency: Duration, span: &Span| {
+            span.record("status", res.status().as_u16());
+            span.record("latency_ms", latency.as_millis() as u64);
+            tracing::info!("request completed");
+        });
+
+    router
+        .layer(PropagateRequestIdLayer::x_request_id())
+        .layer(trace)
+        .layer(SetRequestIdLayer::x_request_id(MakeRequestUuid))
+}
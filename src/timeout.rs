@@ -0,0 +1,67 @@
+use std::{collections::HashMap, sync::Arc, time::Duration};
+
+use axum::{
+    extract::{Request, State},
+    middleware::{self, Next},
+    response::Response,
+    Router,
+};
+
+use crate::config::TimeoutConfig;
+use crate::error::ApiError;
+use crate::limits::route_key;
+use crate::metrics::request_labels;
+
+// 按路由的处理超时。超时后处理函数的 future 被丢弃，
+// 其中未提交的事务随连接归还而回滚；流式响应体的传输不受限制
+#[derive(Clone)]
+pub struct Timeouts {
+    default: Duration,
+    routes: Arc<HashMap<String, Duration>>,
+}
+
+impl Timeouts {
+    pub fn new(config: &TimeoutConfig) -> Self {
+        let routes = config
+            .routes
+            .iter()
+            .map(|route| (route.route.clone(), Duration::from_millis(route.request_ms)))
+            .collect();
+        Timeouts {
+            default: config.request_timeout(),
+            routes: Arc::new(routes),
+        }
+    }
+
+    // 以 route_layer 挂载，只作用于 router 中已有的路由
+    pub fn apply(&self, router: Router) -> Router {
+        router.route_layer(middleware::from_fn_with_state(
+            self.clone(),
+            enforce_timeout,
+        ))
+    }
+}
+
+async fn enforce_timeout(
+    State(timeouts): State<Timeouts>,
+    req: Request,
+    next: Next,
+) -> Result<Response, ApiError> {
+    let limit = timeouts
+        .routes
+        .get(&route_key(&req))
+        .copied()
+        .unwrap_or(timeouts.default);
+    let (method, route) = request_labels(&req);
+    tokio::time::timeout(limit, next.run(req))
+        .await
+        .map_err(|_| {
+            tracing::warn!(%method, %route, timeout_ms = limit.as_millis() as u64, "request timed out");
+            metrics::counter!("http_requests_timed_out_total", "method" => method, "route" => route)
+                .increment(1);
+            ApiError::Timeout(format!(
+                "The request did not complete within {} ms",
+                limit.as_millis()
+            ))
+        })
+}
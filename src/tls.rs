@@ -0,0 +1,647 @@
+use std::{
+    collections::HashMap,
+    convert::Infallible,
+    error::Error,
+    fmt, fs, io,
+    net::SocketAddr,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::{Duration, SystemTime},
+};
+
+use axum::{
+    extract::{ConnectInfo, Request},
+    serve::{IncomingStream, Listener},
+    Router,
+};
+use rustls::{
+    crypto::ring,
+    pki_types::{pem::PemObject, CertificateDer, PrivateKeyDer},
+    server::WebPkiClientVerifier,
+    RootCertStore, ServerConfig,
+};
+use tokio::{
+    net::{TcpListener, TcpStream},
+    sync::{mpsc, watch},
+};
+use tokio_rustls::{server::TlsStream, TlsAcceptor};
+use tower::ServiceExt;
+use x509_parser::{
+    der_parser::Oid,
+    objects::{oid2abbrev, oid_registry},
+    prelude::{FromDer, X509Certificate, X509Name},
+};
+
+use crate::auth::Principal;
+use crate::config::TlsConfig;
+use crate::shutdown::{self, Shutdown};
+
+// 握手未在该时间内完成的连接直接关闭
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+// 已完成握手、等待服务器处理的连接数上限
+const ACCEPT_BACKLOG: usize = 64;
+
+#[derive(Debug)]
+pub enum TlsError {
+    Load { path: PathBuf, message: String },
+    Invalid(String),
+}
+
+impl fmt::Display for TlsError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TlsError::Load { path, message } => {
+                write!(f, "Failed to load {}: {}", path.display(), message)
+            }
+            TlsError::Invalid(msg) => write!(f, "Invalid TLS configuration: {}", msg),
+        }
+    }
+}
+
+impl Error for TlsError {}
+
+fn load_error(path: &Path, message: impl ToString) -> TlsError {
+    TlsError::Load {
+        path: path.to_path_buf(),
+        message: message.to_string(),
+    }
+}
+
+// 证书、私钥和客户端 CA 的文件路径
+#[derive(Clone)]
+struct TlsFiles {
+    cert: PathBuf,
+    key: PathBuf,
+    client_ca: Option<PathBuf>,
+    require_client_cert: bool,
+}
+
+impl TlsFiles {
+    fn new(config: &TlsConfig) -> Option<Self> {
+        Some(TlsFiles {
+            cert: config.cert_path.clone()?,
+            key: config.key_path.clone()?,
+            client_ca: config.client_ca_path.clone(),
+            require_client_cert: config.require_client_cert,
+        })
+    }
+
+    fn paths(&self) -> impl Iterator<Item = &PathBuf> {
+        [&self.cert, &self.key].into_iter().chain(&self.client_ca)
+    }
+
+    // 修改时间和大小，用于判断文件是否被替换
+    fn fingerprint(&self) -> Vec<Option<(SystemTime, u64)>> {
+        self.paths()
+            .map(|path| {
+                let metadata = fs::metadata(path).ok()?;
+                Some((metadata.modified().ok()?, metadata.len()))
+            })
+            .collect()
+    }
+
+    fn load(&self) -> Result<Arc<ServerConfig>, TlsError> {
+        let provider = Arc::new(ring::default_provider());
+        let certs = load_certs(&self.cert)?;
+        let key =
+            PrivateKeyDer::from_pem_file(&self.key).map_err(|err| load_error(&self.key, err))?;
+
+        let builder = ServerConfig::builder_with_provider(provider.clone())
+            .with_safe_default_protocol_versions()
+            .map_err(|err| TlsError::Invalid(err.to_string()))?;
+        let builder = match &self.client_ca {
+            Some(path) => {
+                let mut roots = RootCertStore::empty();
+                for cert in load_certs(path)? {
+                    roots.add(cert).map_err(|err| load_error(path, err))?;
+                }
+                let verifier = WebPkiClientVerifier::builder_with_provider(roots.into(), provider);
+                let verifier = if self.require_client_cert {
+                    verifier
+                } else {
+                    verifier.allow_unauthenticated()
+                };
+                builder.with_client_cert_verifier(
+                    verifier
+                        .build()
+                        .map_err(|err| TlsError::Invalid(err.to_string()))?,
+                )
+            }
+            None => builder.with_no_client_auth(),
+        };
+
+        let mut config = builder
+            .with_single_cert(certs, key)
+            .map_err(|err| TlsError::Invalid(err.to_string()))?;
+        // axum::serve 只启用了 HTTP/1.1
+        config.alpn_protocols = vec![b"http/1.1".to_vec()];
+        Ok(Arc::new(config))
+    }
+}
+
+fn load_certs(path: &Path) -> Result<Vec<CertificateDer<'static>>, TlsError> {
+    let certs = CertificateDer::pem_file_iter(path)
+        .map_err(|err| load_error(path, err))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|err| load_error(path, err))?;
+    if certs.is_empty() {
+        return Err(load_error(path, "no certificates found"));
+    }
+    Ok(certs)
+}
+
+// 定期检查证书文件，变化后重新加载；加载失败时保留当前证书
+async fn watch_files(files: TlsFiles, interval: Duration, tx: watch::Sender<Arc<ServerConfig>>) {
+    let mut fingerprint = files.fingerprint();
+    let mut ticker = tokio::time::interval(interval);
+    ticker.tick().await;
+    loop {
+        tokio::select! {
+            _ = ticker.tick() => {}
+            // 监听器已关闭
+            _ = tx.closed() => return,
+        }
+        let current = files.fingerprint();
+        if current == fingerprint {
+            continue;
+        }
+        // 证书和私钥可能先后写入，加载失败时下一轮再试
+        match files.load() {
+            Ok(config) => {
+                fingerprint = current;
+                tx.send_replace(config);
+                metrics::counter!("tls_reloads_total", "result" => "ok").increment(1);
+                tracing::info!(cert = %files.cert.display(), "reloaded TLS certificate");
+            }
+            Err(err) => {
+                metrics::counter!("tls_reloads_total", "result" => "error").increment(1);
+                tracing::warn!(error = %err, "failed to reload TLS certificate, keeping the current one");
+            }
+        }
+    }
+}
+
+// 对端地址，以及通过 mTLS 认证的客户端
+#[derive(Debug, Clone)]
+pub struct TlsPeer {
+    pub addr: SocketAddr,
+    pub principal: Option<Principal>,
+}
+
+// HTTPS 监听器。握手在后台任务中并发进行，慢客户端不会阻塞其他连接的接入
+pub struct TlsListener {
+    incoming: mpsc::Receiver<(TlsStream<TcpStream>, TlsPeer)>,
+    local_addr: SocketAddr,
+}
+
+impl TlsListener {
+    // 启动时加载证书失败直接返回错误
+    pub fn new(listener: TcpListener, config: &TlsConfig) -> Result<Self, TlsError> {
+        let files = TlsFiles::new(config).ok_or_else(|| {
+            TlsError::Invalid("tls.cert_path and tls.key_path are required".to_string())
+        })?;
+        let local_addr = listener
+            .local_addr()
+            .map_err(|err| TlsError::Invalid(err.to_string()))?;
+        let (config_tx, config_rx) = watch::channel(files.load()?);
+        tokio::spawn(watch_files(files, config.reload_interval(), config_tx));
+
+        let (tx, incoming) = mpsc::channel(ACCEPT_BACKLOG);
+        let roles = Arc::new(client_roles(config));
+        tokio::spawn(accept_loop(listener, config_rx, roles, tx));
+
+        Ok(TlsListener {
+            incoming,
+            local_addr,
+        })
+    }
+}
+
+impl Listener for TlsListener {
+    type Io = TlsStream<TcpStream>;
+    type Addr = TlsPeer;
+
+    async fn accept(&mut self) -> (Self::Io, Self::Addr) {
+        match self.incoming.recv().await {
+            Some(connection) => connection,
+            // 接入任务只在监听器释放后退出，不会走到这里
+            None => std::future::pending().await,
+        }
+    }
+
+    fn local_addr(&self) -> io::Result<Self::Addr> {
+        Ok(TlsPeer {
+            addr: self.local_addr,
+            principal: None,
+        })
+    }
+}
+
+async fn accept_loop(
+    listener: TcpListener,
+    configs: watch::Receiver<Arc<ServerConfig>>,
+    roles: Arc<HashMap<SubjectKey, Vec<String>>>,
+    tx: mpsc::Sender<(TlsStream<TcpStream>, TlsPeer)>,
+) {
+    loop {
+        let (stream, addr) = tokio::select! {
+            result = listener.accept() => match result {
+                Ok(connection) => connection,
+                // 如文件描述符耗尽，稍后重试
+                Err(err) => {
+                    tracing::warn!(error = %err, "failed to accept connection");
+                    tokio::time::sleep(Duration::from_millis(100)).await;
+                    continue;
+                }
+            },
+            // 服务器已停止接收新连接
+            _ = tx.closed() => return,
+        };
+
+        let acceptor = TlsAcceptor::from(configs.borrow().clone());
+        let roles = roles.clone();
+        let tx = tx.clone();
+        tokio::spawn(async move {
+            let stream =
+                match tokio::time::timeout(HANDSHAKE_TIMEOUT, acceptor.accept(stream)).await {
+                    Ok(Ok(stream)) => stream,
+                    Ok(Err(err)) => {
+                        metrics::counter!("tls_handshake_failures_total").increment(1);
+                        tracing::debug!(%addr, error = %err, "TLS handshake failed");
+                        return;
+                    }
+                    Err(_) => {
+                        metrics::counter!("tls_handshake_failures_total").increment(1);
+                        tracing::debug!(%addr, "TLS handshake timed out");
+                        return;
+                    }
+                };
+            let principal = client_principal(&stream, &roles);
+            let _ = tx.send((stream, TlsPeer { addr, principal })).await;
+        });
+    }
+}
+
+// 主题中的一个属性：属性类型的 OID（点分形式）和属性值
+type Attribute = (String, String);
+
+// 与 RDN 顺序无关的证书主题，RDN 及多值 RDN 内的属性都已排序。
+// 证书按编码顺序（通常 C、O 在前）保存 RDN，RFC 4514 字符串则是相反的顺序，
+// 两种写法得到同一个 SubjectKey
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub(crate) struct SubjectKey(Vec<Vec<Attribute>>);
+
+impl SubjectKey {
+    fn new(mut rdns: Vec<Vec<Attribute>>) -> Self {
+        for rdn in &mut rdns {
+            rdn.sort();
+        }
+        rdns.sort();
+        SubjectKey(rdns)
+    }
+}
+
+// 配置中的 subject 对应的角色；Config::validate 已确认 subject 都能解析且互不重复
+fn client_roles(config: &TlsConfig) -> HashMap<SubjectKey, Vec<String>> {
+    config
+        .clients
+        .iter()
+        .filter_map(|client| Some((parse_subject(&client.subject).ok()?, client.roles.clone())))
+        .collect()
+}
+
+// 解析 RFC 4514 形式的主题，如 "CN=ops,O=Example"；逗号后的空格可以省略，RDN 顺序不限
+pub(crate) fn parse_subject(subject: &str) -> Result<SubjectKey, String> {
+    let mut rdns = vec![Vec::new()];
+    let mut part = String::new();
+    let mut chars = subject.chars();
+    loop {
+        let next = chars.next();
+        match next {
+            // 转义保留到 unescape 再处理，这里只跳过被转义的分隔符
+            Some('\\') => {
+                let escaped = chars
+                    .next()
+                    .ok_or_else(|| "the subject ends with an unfinished escape".to_string())?;
+                part.push('\\');
+                part.push(escaped);
+            }
+            Some(',' | '+') | None => {
+                let attribute = parse_attribute(&part)?;
+                if let Some(rdn) = rdns.last_mut() {
+                    rdn.push(attribute);
+                }
+                part.clear();
+                match next {
+                    Some(',') => rdns.push(Vec::new()),
+                    None => break,
+                    _ => {}
+                }
+            }
+            Some(c) => part.push(c),
+        }
+    }
+    Ok(SubjectKey::new(rdns))
+}
+
+fn parse_attribute(part: &str) -> Result<Attribute, String> {
+    if part.trim().is_empty() {
+        return Err("the subject contains an empty RDN".to_string());
+    }
+    let (name, value) = part
+        .split_once('=')
+        .ok_or_else(|| format!("{:?} is not in the form TYPE=value", part.trim()))?;
+    let name = name.trim();
+    let oid = attribute_oid(name).ok_or_else(|| format!("unknown attribute type {:?}", name))?;
+
+    // 去掉两端未转义的空格
+    let value = value.trim_start();
+    let mut end = value.len();
+    while value[..end].ends_with(' ') && !value[..end - 1].ends_with('\\') {
+        end -= 1;
+    }
+    let value = unescape(&value[..end])?;
+    if value.is_empty() {
+        return Err(format!("{} has an empty value", name));
+    }
+    Ok((oid, value))
+}
+
+// 属性类型可以是缩写（CN、O、OU 等，不区分大小写）、OID 注册表中的短名称或点分 OID
+fn attribute_oid(name: &str) -> Option<String> {
+    if name.starts_with(|c: char| c.is_ascii_digit()) {
+        return name.parse::<Oid>().ok().map(|oid| oid.to_id_string());
+    }
+    let registry = oid_registry();
+    registry
+        .iter()
+        .find(|(oid, entry)| {
+            entry.sn().eq_ignore_ascii_case(name)
+                || oid2abbrev(oid, registry).is_ok_and(|abbrev| abbrev.eq_ignore_ascii_case(name))
+        })
+        .map(|(oid, _)| oid.to_id_string())
+}
+
+// 处理 \, 这类转义和 \2C 这类十六进制转义
+fn unescape(value: &str) -> Result<String, String> {
+    let mut bytes = Vec::with_capacity(value.len());
+    let mut rest = value.as_bytes();
+    while let Some((&byte, tail)) = rest.split_first() {
+        rest = tail;
+        if byte != b'\\' {
+            bytes.push(byte);
+            continue;
+        }
+        match rest {
+            [high, low, tail @ ..] if high.is_ascii_hexdigit() && low.is_ascii_hexdigit() => {
+                let hex = [*high, *low];
+                let hex = std::str::from_utf8(&hex).unwrap_or_default();
+                bytes.push(u8::from_str_radix(hex, 16).unwrap_or_default());
+                rest = tail;
+            }
+            [escaped, tail @ ..] => {
+                bytes.push(*escaped);
+                rest = tail;
+            }
+            [] => return Err("the subject ends with an unfinished escape".to_string()),
+        }
+    }
+    String::from_utf8(bytes).map_err(|_| "escaped value is not valid UTF-8".to_string())
+}
+
+// 证书主题的 RFC 4514 字符串（与编码顺序相反）及用于匹配配置的 SubjectKey
+fn certificate_subject(name: &X509Name) -> (String, SubjectKey) {
+    let registry = oid_registry();
+    let mut rdns = Vec::new();
+    let mut parts = Vec::new();
+    for rdn in name.iter() {
+        let mut attributes = Vec::new();
+        let mut part = Vec::new();
+        for attribute in rdn.iter() {
+            let oid = attribute.attr_type();
+            // 非字符串类型的值按 RFC 4514 以 # 加十六进制表示
+            let value = match attribute.as_str() {
+                Ok(value) => value.to_string(),
+                Err(_) => attribute
+                    .as_slice()
+                    .iter()
+                    .fold("#".to_string(), |hex, byte| hex + &format!("{:02x}", byte)),
+            };
+            let label =
+                oid2abbrev(oid, registry).map_or_else(|_| oid.to_id_string(), str::to_string);
+            part.push(format!("{}={}", label, escape(&value)));
+            attributes.push((oid.to_id_string(), value));
+        }
+        parts.push(part.join("+"));
+        rdns.push(attributes);
+    }
+    parts.reverse();
+    (parts.join(","), SubjectKey::new(rdns))
+}
+
+// RFC 4514 第 2.4 节要求转义的字符
+fn escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for (index, c) in value.char_indices() {
+        let at_start = index == 0 && matches!(c, ' ' | '#');
+        let at_end = index + c.len_utf8() == value.len() && c == ' ';
+        if at_start || at_end || matches!(c, '"' | '+' | ',' | ';' | '<' | '>' | '\\') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+// 客户端证书已由 CA 校验，以 RFC 4514 形式的证书主题作为 subject、CN 作为 name
+fn client_principal(
+    stream: &TlsStream<TcpStream>,
+    roles: &HashMap<SubjectKey, Vec<String>>,
+) -> Option<Principal> {
+    let der = stream.get_ref().1.peer_certificates()?.first()?;
+    let (_, cert) = X509Certificate::from_der(der).ok()?;
+    let (subject, key) = certificate_subject(cert.subject());
+    let name = cert
+        .subject()
+        .iter_common_name()
+        .next()
+        .and_then(|cn| cn.as_str().ok())
+        .map(str::to_string);
+    tracing::debug!(%subject, "client certificate accepted");
+    Some(Principal {
+        roles: roles.get(&key).cloned().unwrap_or_default(),
+        subject,
+        name,
+    })
+}
+
+// 以 HTTPS 提供服务，关闭流程与 shutdown::serve 相同。
+// 每个请求携带 ConnectInfo<SocketAddr>，mTLS 认证的连接还会携带 Principal
+pub async fn serve(
+    listener: TlsListener,
+    app: Router,
+    shutdown: Shutdown,
+    drain_timeout: Duration,
+) -> io::Result<()> {
+    let make_service = tower::service_fn(move |incoming: IncomingStream<'_, TlsListener>| {
+        let TlsPeer { addr, principal } = incoming.remote_addr().clone();
+        let app = app.clone().map_request(move |mut req: Request| {
+            req.extensions_mut().insert(ConnectInfo(addr));
+            if let Some(principal) = &principal {
+                req.extensions_mut().insert(principal.clone());
+            }
+            req
+        });
+        std::future::ready(Ok::<_, Infallible>(app))
+    });
+    let server = axum::serve(listener, make_service).with_graceful_shutdown(shutdown.signaled());
+    shutdown::drain(server, &shutdown, drain_timeout).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CN: &str = "2.5.4.3";
+    const O: &str = "2.5.4.10";
+    const OU: &str = "2.5.4.11";
+
+    fn rdn(attributes: &[(&str, &str)]) -> Vec<Attribute> {
+        attributes
+            .iter()
+            .map(|(oid, value)| (oid.to_string(), value.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn subjects_match_regardless_of_rdn_order_and_type_spelling() {
+        let expected = SubjectKey::new(vec![rdn(&[(CN, "ops")]), rdn(&[(O, "Example")])]);
+        for subject in [
+            "CN=ops,O=Example",
+            "O=Example, CN=ops",
+            "cn=ops , o=Example",
+            "commonName=ops,organizationName=Example",
+            "2.5.4.3=ops,2.5.4.10=Example",
+        ] {
+            assert_eq!(parse_subject(subject), Ok(expected.clone()), "{}", subject);
+        }
+    }
+
+    #[test]
+    fn multi_valued_rdns_are_kept_together() {
+        let multi = parse_subject("CN=ops+OU=Infra,O=Example").unwrap();
+        assert_eq!(
+            multi,
+            SubjectKey::new(vec![
+                rdn(&[(CN, "ops"), (OU, "Infra")]),
+                rdn(&[(O, "Example")]),
+            ])
+        );
+        assert_eq!(
+            parse_subject("O=Example,OU=Infra+CN=ops"),
+            Ok(multi.clone())
+        );
+        assert_ne!(parse_subject("CN=ops,OU=Infra,O=Example"), Ok(multi));
+    }
+
+    #[test]
+    fn escaped_separators_belong_to_the_value() {
+        assert_eq!(
+            parse_subject(r"CN=ops\+dev,O=Example\, Inc."),
+            Ok(SubjectKey::new(vec![
+                rdn(&[(CN, "ops+dev")]),
+                rdn(&[(O, "Example, Inc.")]),
+            ]))
+        );
+        // 转义的尾部空格保留，未转义的去掉
+        assert_eq!(
+            parse_subject(r"CN= ops\ ,O=Example "),
+            Ok(SubjectKey::new(vec![
+                rdn(&[(CN, "ops ")]),
+                rdn(&[(O, "Example")]),
+            ]))
+        );
+    }
+
+    #[test]
+    fn malformed_subjects_are_rejected() {
+        for (subject, reason) in [
+            ("", "the subject contains an empty RDN"),
+            ("CN=ops,", "the subject contains an empty RDN"),
+            ("CN=ops,,O=Example", "the subject contains an empty RDN"),
+            ("CN=ops\\", "the subject ends with an unfinished escape"),
+            ("CN ops", "\"CN ops\" is not in the form TYPE=value"),
+            ("Common=ops", "unknown attribute type \"Common\""),
+            ("CN= ", "CN has an empty value"),
+        ] {
+            assert_eq!(
+                parse_subject(subject),
+                Err(reason.to_string()),
+                "{}",
+                subject
+            );
+        }
+    }
+
+    #[test]
+    fn unescape_handles_character_and_hex_escapes() {
+        assert_eq!(unescape(r"a\,b\+c\\d"), Ok(r"a,b+c\d".to_string()));
+        assert_eq!(unescape(r"a\2Cb"), Ok("a,b".to_string()));
+        assert_eq!(unescape(r"caf\C3\A9"), Ok("café".to_string()));
+        // 不是两位十六进制数时按普通转义处理
+        assert_eq!(unescape(r"\Gx"), Ok("Gx".to_string()));
+        assert_eq!(
+            unescape(r"\FF"),
+            Err("escaped value is not valid UTF-8".to_string())
+        );
+        assert_eq!(
+            unescape("a\\"),
+            Err("the subject ends with an unfinished escape".to_string())
+        );
+    }
+
+    fn der(tag: u8, content: &[u8]) -> Vec<u8> {
+        assert!(content.len() < 0x80);
+        let mut encoded = vec![tag, content.len() as u8];
+        encoded.extend_from_slice(content);
+        encoded
+    }
+
+    // 按给定顺序编码的 Name，属性类型为 2.5.4.x，值为 UTF8String
+    fn name(rdns: &[&[(u8, &str)]]) -> Vec<u8> {
+        let rdns: Vec<u8> = rdns
+            .iter()
+            .flat_map(|attributes| {
+                let set: Vec<u8> = attributes
+                    .iter()
+                    .flat_map(|(arc, value)| {
+                        let mut attribute = der(0x06, &[0x55, 0x04, *arc]);
+                        attribute.extend(der(0x0c, value.as_bytes()));
+                        der(0x30, &attribute)
+                    })
+                    .collect();
+                der(0x31, &set)
+            })
+            .collect();
+        der(0x30, &rdns)
+    }
+
+    #[test]
+    fn certificate_subjects_match_configured_subjects_in_any_order() {
+        let encoded = name(&[&[(10, "Example, Inc.")], &[(3, "ops"), (11, "Infra")]]);
+        let (_, name) = X509Name::from_der(&encoded).unwrap();
+        let (subject, key) = certificate_subject(&name);
+
+        // RFC 4514 字符串与编码顺序相反
+        assert_eq!(subject, r"CN=ops+OU=Infra,O=Example\, Inc.");
+        for configured in [
+            subject.as_str(),
+            r"O=Example\, Inc.,OU=Infra+CN=ops",
+            r"ou=Infra+cn=ops, o=Example\2C Inc.",
+        ] {
+            assert_eq!(parse_subject(configured), Ok(key.clone()), "{}", configured);
+        }
+        assert_ne!(parse_subject(r"CN=ops,OU=Infra,O=Example\, Inc."), Ok(key));
+    }
+}
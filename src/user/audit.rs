@@ -0,0 +1,216 @@
+use std::convert::Infallible;
+
+use axum::{extract::FromRequestParts, http::request::Parts};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use utoipa::{IntoParams, ToSchema};
+
+use super::model::User;
+use super::query::Page;
+use crate::auth::Principal;
+use crate::error::{ApiError, FieldError};
+
+pub const DEFAULT_HISTORY_LIMIT: u32 = 50;
+pub const MAX_HISTORY_LIMIT: u32 = 200;
+
+// 审计日志中记录的变更类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditAction {
+    Create,
+    Replace,
+    Delete,
+    Restore,
+    Purge,
+}
+
+impl AuditAction {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            AuditAction::Create => "create",
+            AuditAction::Replace => "replace",
+            AuditAction::Delete => "delete",
+            AuditAction::Restore => "restore",
+            AuditAction::Purge => "purge",
+        }
+    }
+}
+
+// 发起变更的调用方和请求，未认证的请求 actor 为 None
+#[derive(Debug, Clone, Default)]
+pub struct AuditContext {
+    pub actor: Option<String>,
+    pub request_id: Option<String>,
+}
+
+impl AuditContext {
+    // 非请求触发的变更，如启动时写入的初始数据
+    pub fn system(actor: &str) -> Self {
+        AuditContext {
+            actor: Some(actor.to_string()),
+            request_id: None,
+        }
+    }
+}
+
+impl<S: Send + Sync> FromRequestParts<S> for AuditContext {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let actor = parts
+            .extensions
+            .get::<Principal>()
+            .map(|principal| principal.subject.clone());
+        let request_id = parts
+            .headers
+            .get("x-request-id")
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string);
+        Ok(AuditContext { actor, request_id })
+    }
+}
+
+// 审计关注的用户字段，未删除的用户不包含 deleted_at
+pub fn snapshot(user: &User, deleted_at: Option<DateTime<Utc>>) -> Value {
+    let mut fields = Map::new();
+    fields.insert("name".to_string(), Value::String(user.name.clone()));
+    fields.insert("email".to_string(), Value::String(user.email.clone()));
+    if let Some(deleted_at) = deleted_at {
+        fields.insert("deleted_at".to_string(), serde_json::json!(deleted_at));
+    }
+    Value::Object(fields)
+}
+
+// 待写入的审计记录，before/after 只保留发生变化的字段
+pub struct AuditRecord {
+    pub user_id: i32,
+    pub action: AuditAction,
+    pub before: Option<Value>,
+    pub after: Option<Value>,
+}
+
+impl AuditRecord {
+    pub fn new(
+        user_id: i32,
+        action: AuditAction,
+        before: Option<Value>,
+        after: Option<Value>,
+    ) -> Self {
+        let (before, after) = match (before, after) {
+            (Some(Value::Object(before)), Some(Value::Object(after))) => {
+                let (before, after) = diff(before, after);
+                (Some(Value::Object(before)), Some(Value::Object(after)))
+            }
+            other => other,
+        };
+        AuditRecord {
+            user_id,
+            action,
+            before,
+            after,
+        }
+    }
+}
+
+// 一侧缺少的字段按 null 处理
+fn diff(
+    mut before: Map<String, Value>,
+    mut after: Map<String, Value>,
+) -> (Map<String, Value>, Map<String, Value>) {
+    let keys: Vec<String> = before.keys().chain(after.keys()).cloned().collect();
+    let mut changed_before = Map::new();
+    let mut changed_after = Map::new();
+    for key in keys {
+        let old = before.remove(&key).unwrap_or(Value::Null);
+        let new = after.remove(&key).unwrap_or(Value::Null);
+        if old != new {
+            changed_before.insert(key.clone(), old);
+            changed_after.insert(key, new);
+        }
+    }
+    (changed_before, changed_after)
+}
+
+// 已写入的审计记录
+#[derive(Clone, Serialize, sqlx::FromRow, ToSchema)]
+pub struct AuditEntry {
+    pub id: i64,
+    pub user_id: i32,
+    #[schema(example = "replace")]
+    pub action: String,
+    pub actor: Option<String>,
+    pub request_id: Option<String>,
+    pub before: Option<Value>,
+    pub after: Option<Value>,
+    pub created_at: DateTime<Utc>,
+}
+
+// 按 id 倒序分页，before_id 为上一页最后一条的 id
+#[derive(Debug, Clone)]
+pub struct HistoryQuery {
+    pub before_id: Option<i64>,
+    pub limit: u32,
+}
+
+// GET /api/admin/users/{id}/history 的查询参数
+#[derive(Debug, Default, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct HistoryParams {
+    #[param(minimum = 1, maximum = 200, default = 50)]
+    pub limit: Option<u32>,
+    /// 上一页响应中的 next_cursor
+    pub after: Option<String>,
+}
+
+impl HistoryParams {
+    pub fn into_query(self) -> Result<HistoryQuery, ApiError> {
+        let mut errors = Vec::new();
+
+        let limit = self.limit.unwrap_or(DEFAULT_HISTORY_LIMIT);
+        if !(1..=MAX_HISTORY_LIMIT).contains(&limit) {
+            errors.push(FieldError::new(
+                "limit",
+                "range",
+                format!("must be between 1 and {}", MAX_HISTORY_LIMIT),
+            ));
+        }
+
+        let before_id = match &self.after {
+            None => None,
+            Some(raw) => {
+                let id = raw.parse::<i64>().ok();
+                if id.is_none() {
+                    errors.push(FieldError::new(
+                        "after",
+                        "invalid_cursor",
+                        "is not a valid cursor",
+                    ));
+                }
+                id
+            }
+        };
+
+        if !errors.is_empty() {
+            return Err(ApiError::Validation(errors));
+        }
+        Ok(HistoryQuery { before_id, limit })
+    }
+}
+
+impl Page<AuditEntry> {
+    // rows 需按 query 查询 limit + 1 条，多出的一条表示还有下一页
+    pub fn from_entries(mut rows: Vec<AuditEntry>, query: &HistoryQuery) -> Self {
+        let limit = query.limit as usize;
+        let next_cursor = if rows.len() > limit {
+            rows.truncate(limit);
+            rows.last().map(|entry| entry.id.to_string())
+        } else {
+            None
+        };
+        Page {
+            items: rows,
+            next_cursor,
+        }
+    }
+}
@@ -0,0 +1,376 @@
+use axum::{
+    body::{Body, Bytes},
+    extract::{FromRequest, Request},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+};
+use futures::{stream, Stream, TryStreamExt};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use utoipa::{IntoParams, ToSchema};
+
+use super::audit::AuditContext;
+use super::events::UserEvents;
+use super::model::{CreateUserRequest, User};
+use super::query::{Cursor, Sort, SortField, UserFilter, UserQuery};
+use super::store::{CreateOutcome, StoreError, UserStore};
+use crate::error::{ApiError, FieldError};
+use crate::extract::{from_value, typed_body};
+
+pub const NDJSON: &str = "application/x-ndjson";
+pub const CSV: &str = "text/csv";
+
+// 每条 INSERT 写入的行数
+pub const IMPORT_BATCH_SIZE: usize = 1000;
+// 单次导入允许的最大行数
+pub const MAX_IMPORT_ROWS: usize = 100_000;
+// 导入请求体上限，替代 axum 默认的 2 MiB
+pub const IMPORT_BODY_LIMIT: usize = 32 * 1024 * 1024;
+// 导出时每次从存储读取的行数
+pub const EXPORT_PAGE_SIZE: u32 = 500;
+
+const CSV_COLUMNS: [&str; 6] = ["id", "name", "email", "created_at", "updated_at", "version"];
+
+// 导入的一行，row 为 NDJSON 的行号或 CSV 的记录行号（从 1 开始，含表头）
+pub struct ImportRow {
+    pub row: u64,
+    pub payload: Result<CreateUserRequest, ApiError>,
+}
+
+// POST /api/users:bulk 的请求体，按 Content-Type 解析为 NDJSON 或 CSV
+pub struct ImportRows(pub Vec<ImportRow>);
+
+impl<S: Send + Sync> FromRequest<S> for ImportRows {
+    type Rejection = ApiError;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let (content_type, body) = typed_body(req, state).await?;
+        let rows = match content_type.as_str() {
+            NDJSON | "application/ndjson" => parse_ndjson(&body),
+            CSV => parse_csv(&body)?,
+            _ => {
+                return Err(ApiError::Rejected {
+                    status: StatusCode::UNSUPPORTED_MEDIA_TYPE,
+                    message: format!("Expected Content-Type {} or {}", NDJSON, CSV),
+                })
+            }
+        };
+
+        if rows.len() > MAX_IMPORT_ROWS {
+            return Err(ApiError::Rejected {
+                status: StatusCode::PAYLOAD_TOO_LARGE,
+                message: format!("At most {} rows can be imported at once", MAX_IMPORT_ROWS),
+            });
+        }
+        Ok(ImportRows(rows))
+    }
+}
+
+fn malformed_row(message: String) -> ApiError {
+    ApiError::Rejected {
+        status: StatusCode::UNPROCESSABLE_ENTITY,
+        message,
+    }
+}
+
+// 每行一个 JSON 对象，空行忽略
+fn parse_ndjson(body: &[u8]) -> Vec<ImportRow> {
+    body.split(|&byte| byte == b'\n')
+        .enumerate()
+        .filter(|(_, line)| !line.trim_ascii().is_empty())
+        .map(|(index, line)| {
+            let payload = serde_json::from_slice::<Value>(line)
+                .map_err(|err| malformed_row(format!("Failed to parse the JSON line: {}", err)))
+                .and_then(from_value);
+            ImportRow {
+                row: index as u64 + 1,
+                payload,
+            }
+        })
+        .collect()
+}
+
+// 首行为表头，列名即字段名；缺少必需列时整个请求返回 422
+fn parse_csv(body: &[u8]) -> Result<Vec<ImportRow>, ApiError> {
+    let mut reader = csv::ReaderBuilder::new()
+        .trim(csv::Trim::Headers)
+        .from_reader(body);
+    let headers = reader
+        .headers()
+        .map_err(|err| ApiError::Rejected {
+            status: StatusCode::BAD_REQUEST,
+            message: format!("Failed to read the CSV header: {}", err),
+        })?
+        .clone();
+
+    let missing: Vec<FieldError> = ["name", "email"]
+        .into_iter()
+        .filter(|column| !headers.iter().any(|header| header == *column))
+        .map(|column| FieldError::new(column, "missing_column", "is a required CSV column"))
+        .collect();
+    if !missing.is_empty() {
+        return Err(ApiError::Validation(missing));
+    }
+
+    let rows = reader
+        .records()
+        .map(|record| match record {
+            Ok(record) => {
+                let row = record.position().map_or(0, |position| position.line());
+                let object: Map<String, Value> = headers
+                    .iter()
+                    .zip(record.iter())
+                    .map(|(column, value)| (column.to_string(), Value::String(value.to_string())))
+                    .collect();
+                ImportRow {
+                    row,
+                    payload: from_value(Value::Object(object)),
+                }
+            }
+            Err(err) => ImportRow {
+                row: err.position().map_or(0, |position| position.line()),
+                payload: Err(malformed_row(format!("Malformed CSV record: {}", err))),
+            },
+        })
+        .collect();
+    Ok(rows)
+}
+
+// 单行的导入结果
+#[derive(Serialize, ToSchema)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum RowResult {
+    Created { row: u64, id: i32 },
+    Failed { row: u64, error: ApiError },
+}
+
+// 导入报告，results 与输入行一一对应
+#[derive(Serialize, ToSchema)]
+pub struct ImportReport {
+    pub total: usize,
+    pub created: usize,
+    pub failed: usize,
+    pub results: Vec<RowResult>,
+}
+
+// 校验失败的行直接记为失败，其余按批次写入；已写入的批次不会因后续失败回滚，
+// 每个批次写入后即推送其中新建用户的事件。某个批次写入失败时停止导入，
+// 该批次及之后的行都记为失败，报告仍然反映哪些行已经写入
+pub async fn import<S: UserStore>(
+    store: &S,
+    rows: Vec<ImportRow>,
+    ctx: &AuditContext,
+    events: &UserEvents,
+) -> ImportReport {
+    let total = rows.len();
+    let mut results = Vec::with_capacity(total);
+    let mut valid_rows = Vec::new();
+    let mut inputs = Vec::new();
+    for ImportRow { row, payload } in rows {
+        match payload {
+            Ok(input) => {
+                valid_rows.push(row);
+                inputs.push(input);
+            }
+            Err(error) => results.push(RowResult::Failed { row, error }),
+        }
+    }
+
+    let mut aborted: Option<String> = None;
+    for (batch_rows, batch) in valid_rows
+        .chunks(IMPORT_BATCH_SIZE)
+        .zip(inputs.chunks(IMPORT_BATCH_SIZE))
+    {
+        if aborted.is_none() {
+            match store.create_users(batch, ctx).await {
+                Ok(outcomes) => {
+                    push_outcomes(&mut results, batch_rows, outcomes, events);
+                    continue;
+                }
+                Err(err) => {
+                    let error = ApiError::from(err);
+                    if let ApiError::Internal(detail) = &error {
+                        tracing::error!(error = %detail, "bulk import aborted");
+                    }
+                    aborted = Some(error.to_string());
+                }
+            }
+        }
+        let reason = aborted.as_deref().unwrap_or_default();
+        results.extend(batch_rows.iter().map(|&row| RowResult::Failed {
+            row,
+            error: ApiError::Unavailable(format!("Not imported, the import stopped: {}", reason)),
+        }));
+    }
+
+    results.sort_by_key(|result| match result {
+        RowResult::Created { row, .. } | RowResult::Failed { row, .. } => *row,
+    });
+    let created = results
+        .iter()
+        .filter(|result| matches!(result, RowResult::Created { .. }))
+        .count();
+    ImportReport {
+        total,
+        created,
+        failed: total - created,
+        results,
+    }
+}
+
+fn push_outcomes(
+    results: &mut Vec<RowResult>,
+    rows: &[u64],
+    outcomes: Vec<CreateOutcome>,
+    events: &UserEvents,
+) {
+    for (&row, outcome) in rows.iter().zip(outcomes) {
+        results.push(match outcome {
+            Ok((user, event)) => {
+                events.publish(event);
+                RowResult::Created { row, id: user.id }
+            }
+            Err(err) => RowResult::Failed {
+                row,
+                error: err.into(),
+            },
+        });
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    #[default]
+    Ndjson,
+    Csv,
+}
+
+// GET /api/users:export 的查询参数
+#[derive(Debug, Default, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ExportParams {
+    #[serde(default)]
+    #[param(inline)]
+    pub format: ExportFormat,
+    /// 名称前缀，区分大小写
+    pub name_prefix: Option<String>,
+    /// 邮箱域名，不区分大小写，如 example.com
+    pub email_domain: Option<String>,
+}
+
+impl ExportFormat {
+    fn content_type(self) -> &'static str {
+        match self {
+            ExportFormat::Ndjson => NDJSON,
+            ExportFormat::Csv => "text/csv; charset=utf-8",
+        }
+    }
+
+    fn file_name(self) -> &'static str {
+        match self {
+            ExportFormat::Ndjson => "users.ndjson",
+            ExportFormat::Csv => "users.csv",
+        }
+    }
+
+    fn encode(self, users: &[User], header: bool) -> Bytes {
+        match self {
+            ExportFormat::Ndjson => {
+                let mut buf = Vec::new();
+                for user in users {
+                    serde_json::to_writer(&mut buf, user).expect("user is always serializable");
+                    buf.push(b'\n');
+                }
+                buf.into()
+            }
+            ExportFormat::Csv => {
+                let mut writer = csv::WriterBuilder::new()
+                    .has_headers(false)
+                    .from_writer(Vec::new());
+                if header {
+                    writer
+                        .write_record(CSV_COLUMNS)
+                        .expect("writing to a Vec cannot fail");
+                }
+                for user in users {
+                    writer
+                        .serialize(user)
+                        .expect("writing to a Vec cannot fail");
+                }
+                writer
+                    .into_inner()
+                    .expect("writing to a Vec cannot fail")
+                    .into()
+            }
+        }
+    }
+}
+
+struct ExportState<S> {
+    store: S,
+    query: UserQuery,
+    first: bool,
+    done: bool,
+}
+
+// 按 id 键集分页逐页读取并编码，内存中最多保留一页
+pub fn export_stream<S: UserStore>(
+    store: S,
+    filter: UserFilter,
+    format: ExportFormat,
+) -> impl Stream<Item = Result<Bytes, StoreError>> + Send {
+    let state = ExportState {
+        store,
+        query: UserQuery {
+            filter,
+            sort: Sort {
+                field: SortField::Id,
+                descending: false,
+            },
+            after: None,
+            limit: EXPORT_PAGE_SIZE,
+        },
+        first: true,
+        done: false,
+    };
+
+    stream::try_unfold(state, move |mut state| async move {
+        if state.done {
+            return Ok(None);
+        }
+        let users = state.store.list_users(&state.query).await?;
+        state.done = users.len() < state.query.limit as usize;
+        if let Some(last) = users.last() {
+            state.query.after = Some(Cursor::for_user(state.query.sort, last));
+        }
+
+        let chunk = format.encode(&users, state.first);
+        state.first = false;
+        Ok(Some((chunk, state)))
+    })
+}
+
+// 流式导出响应，读取失败时记录日志并中断连接
+pub fn export_response<S: UserStore>(store: S, params: ExportParams) -> Response {
+    let filter = UserFilter {
+        name_prefix: params.name_prefix.filter(|prefix| !prefix.is_empty()),
+        email_domain: params.email_domain.filter(|domain| !domain.is_empty()),
+    };
+    let format = params.format;
+    let body = export_stream(store, filter, format).inspect_err(|err| {
+        tracing::error!(error = %err, "user export failed");
+    });
+
+    (
+        [
+            (header::CONTENT_TYPE, format.content_type().to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!(r#"attachment; filename="{}""#, format.file_name()),
+            ),
+        ],
+        Body::from_stream(body),
+    )
+        .into_response()
+}
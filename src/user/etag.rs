@@ -0,0 +1,66 @@
+use axum::{
+    extract::FromRequestParts,
+    http::{header, request::Parts, HeaderName, HeaderValue},
+};
+
+use super::model::User;
+use super::store::Precondition;
+use crate::error::ApiError;
+
+// 强校验 ETag，取值为用户的版本号，如 "3"
+pub fn etag(user: &User) -> HeaderValue {
+    HeaderValue::from_str(&format!("\"{}\"", user.version)).expect("etag is valid header value")
+}
+
+pub fn etag_header(user: &User) -> [(HeaderName, HeaderValue); 1] {
+    [(header::ETAG, etag(user))]
+}
+
+// If-Match 请求头，未携带时为 None
+pub struct IfMatch(pub Option<Precondition>);
+
+impl IfMatch {
+    // 严格模式下必须携带 If-Match，否则返回 428
+    pub fn into_precondition(self, required: bool) -> Result<Precondition, ApiError> {
+        match self.0 {
+            Some(precondition) => Ok(precondition),
+            None if required => Err(ApiError::PreconditionRequired(
+                "This request requires an If-Match header".to_string(),
+            )),
+            None => Ok(Precondition::Any),
+        }
+    }
+}
+
+impl<S: Send + Sync> FromRequestParts<S> for IfMatch {
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let mut values = parts.headers.get_all(header::IF_MATCH).iter().peekable();
+        if values.peek().is_none() {
+            return Ok(IfMatch(None));
+        }
+
+        let mut versions = Vec::new();
+        for value in values {
+            let value = value.to_str().map_err(|_| ApiError::PreconditionFailed(
+                "If-Match header is not valid".to_string(),
+            ))?;
+            for tag in value.split(',').map(str::trim) {
+                if tag == "*" {
+                    return Ok(IfMatch(Some(Precondition::Any)));
+                }
+                // If-Match 使用强比较，弱 ETag 和无法识别的值都不会匹配
+                if let Some(version) = tag
+                    .strip_prefix('"')
+                    .and_then(|tag| tag.strip_suffix('"'))
+                    .and_then(|tag| tag.parse().ok())
+                {
+                    versions.push(version);
+                }
+            }
+        }
+
+        Ok(IfMatch(Some(Precondition::Versions(versions))))
+    }
+}
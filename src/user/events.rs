@@ -0,0 +1,137 @@
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Mutex},
+};
+
+use serde::Serialize;
+use serde_json::Value;
+use tokio::sync::broadcast;
+use utoipa::ToSchema;
+
+use super::outbox::{UserEvent, UserEventType};
+use crate::shutdown::Shutdown;
+
+// 重放缓冲区默认保留的事件数
+pub const DEFAULT_REPLAY_CAPACITY: usize = 1024;
+
+// 推送给 SSE/WebSocket 客户端的事件，seq 在进程内单调递增，用作 Last-Event-ID
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct StreamEvent {
+    pub seq: u64,
+    #[serde(rename = "type")]
+    pub event_type: UserEventType,
+    pub payload: Value,
+}
+
+// 进程内的用户变更广播，保留最近的事件供断线重连的客户端补发
+#[derive(Clone)]
+pub struct UserEvents {
+    inner: Arc<Mutex<Inner>>,
+}
+
+struct Inner {
+    // close 后为 None，订阅者的接收端随之结束
+    tx: Option<broadcast::Sender<StreamEvent>>,
+    next_seq: u64,
+    buffer: VecDeque<StreamEvent>,
+    capacity: usize,
+}
+
+// 新订阅：先发送 replay，再从 receiver 接收后续事件，两者之间不会遗漏或重复
+pub struct Subscription {
+    pub replay: Vec<StreamEvent>,
+    // Last-Event-ID 之后的事件已不在缓冲区中（或来自重启前），客户端需要重新拉取列表
+    pub gap: bool,
+    pub receiver: broadcast::Receiver<StreamEvent>,
+}
+
+impl UserEvents {
+    pub fn new(capacity: usize) -> Self {
+        let capacity = capacity.max(1);
+        let (tx, _) = broadcast::channel(capacity);
+        UserEvents {
+            inner: Arc::new(Mutex::new(Inner {
+                tx: Some(tx),
+                next_seq: 1,
+                buffer: VecDeque::with_capacity(capacity),
+                capacity,
+            })),
+        }
+    }
+
+    pub fn publish(&self, event: UserEvent) {
+        let mut inner = self.inner.lock().unwrap();
+        let event = StreamEvent {
+            seq: inner.next_seq,
+            event_type: event.event_type,
+            payload: event.payload(),
+        };
+        inner.next_seq += 1;
+        if inner.buffer.len() == inner.capacity {
+            inner.buffer.pop_front();
+        }
+        inner.buffer.push_back(event.clone());
+        // 持锁发送，保证与 subscribe 的补发不交错；没有订阅者时发送失败可以忽略
+        if let Some(tx) = &inner.tx {
+            let _ = tx.send(event);
+        }
+    }
+
+    // 结束所有事件流，之后的订阅会立即结束
+    pub fn close(&self) {
+        self.inner.lock().unwrap().tx = None;
+    }
+
+    // 收到关闭信号时 close，避免 SSE/WebSocket 长连接拖住连接排空
+    pub fn close_on(&self, shutdown: &Shutdown) {
+        let events = self.clone();
+        let shutdown = shutdown.clone();
+        tokio::spawn(async move {
+            shutdown.wait().await;
+            events.close();
+        });
+    }
+
+    // last_seen 为客户端收到的最后一个 seq，None 表示只接收新事件
+    pub fn subscribe(&self, last_seen: Option<u64>) -> Subscription {
+        let inner = self.inner.lock().unwrap();
+        let receiver = match &inner.tx {
+            Some(tx) => tx.subscribe(),
+            None => broadcast::channel(1).1,
+        };
+        let Some(last_seen) = last_seen else {
+            return Subscription {
+                replay: Vec::new(),
+                gap: false,
+                receiver,
+            };
+        };
+
+        let oldest = inner
+            .buffer
+            .front()
+            .map_or(inner.next_seq, |event| event.seq);
+        let gap = last_seen + 1 < oldest || last_seen >= inner.next_seq;
+        let replay = if gap {
+            Vec::new()
+        } else {
+            inner
+                .buffer
+                .iter()
+                .filter(|event| event.seq > last_seen)
+                .cloned()
+                .collect()
+        };
+        Subscription {
+            replay,
+            gap,
+            receiver,
+        }
+    }
+}
+
+impl Default for UserEvents {
+    fn default() -> Self {
+        Self::new(DEFAULT_REPLAY_CAPACITY)
+    }
+}
@@ -0,0 +1,326 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::Arc;
+
+use chrono::{DateTime, SubsecRound, Utc};
+use tokio::sync::RwLock;
+
+use super::audit::{snapshot, AuditAction, AuditContext, AuditEntry, AuditRecord, HistoryQuery};
+use super::model::{CreateUserRequest, User};
+use super::outbox::{UserEvent, UserEventType};
+use super::query::UserQuery;
+use super::store::{CreateOutcome, Precondition, StoreError, UserStore};
+
+// 与 Postgres 的 TIMESTAMPTZ 精度保持一致（微秒）
+fn now() -> DateTime<Utc> {
+    Utc::now().trunc_subsecs(6)
+}
+
+struct Inner {
+    users: HashMap<i32, User>,
+    // 软删除的用户及其删除时间，对常规查询不可见
+    deleted: HashMap<i32, (User, DateTime<Utc>)>,
+    next_id: i32,
+    // 有界审计日志，超出容量时丢弃最早的记录
+    audit: VecDeque<AuditEntry>,
+    audit_capacity: usize,
+    next_audit_id: i64,
+}
+
+impl Inner {
+    // 模拟 users.email 上的唯一约束
+    fn check_email(&self, email: &str, except_id: Option<i32>) -> Result<(), StoreError> {
+        let taken = self
+            .users
+            .values()
+            .any(|user| user.email == email && Some(user.id) != except_id);
+        if taken {
+            return Err(StoreError::Conflict("email".to_string()));
+        }
+        Ok(())
+    }
+
+    // 调用方需先确认邮箱未被占用
+    fn insert(&mut self, input: &CreateUserRequest) -> User {
+        let id = self.next_id;
+        self.next_id += 1;
+
+        let now = now();
+        let user = User {
+            id,
+            name: input.name.clone(),
+            email: input.email.clone(),
+            created_at: now,
+            updated_at: now,
+            version: 1,
+        };
+
+        self.users.insert(id, user.clone());
+        user
+    }
+
+    fn record(&mut self, ctx: &AuditContext, record: AuditRecord) {
+        if self.audit_capacity == 0 {
+            return;
+        }
+        if self.audit.len() == self.audit_capacity {
+            self.audit.pop_front();
+        }
+
+        let id = self.next_audit_id;
+        self.next_audit_id += 1;
+        self.audit.push_back(AuditEntry {
+            id,
+            user_id: record.user_id,
+            action: record.action.as_str().to_string(),
+            actor: ctx.actor.clone(),
+            request_id: ctx.request_id.clone(),
+            before: record.before,
+            after: record.after,
+            created_at: now(),
+        });
+    }
+}
+
+// 内存审计日志默认保留的记录数
+pub const DEFAULT_AUDIT_CAPACITY: usize = 10_000;
+
+// 基于内存的用户存储，没有 outbox，写操作返回的事件只用于进程内推送
+#[derive(Clone)]
+pub struct InMemoryUserStore {
+    inner: Arc<RwLock<Inner>>,
+}
+
+impl InMemoryUserStore {
+    pub fn new() -> Self {
+        Self::with_audit_capacity(DEFAULT_AUDIT_CAPACITY)
+    }
+
+    pub fn with_audit_capacity(capacity: usize) -> Self {
+        InMemoryUserStore {
+            inner: Arc::new(RwLock::new(Inner {
+                users: HashMap::new(),
+                deleted: HashMap::new(),
+                next_id: 1,
+                audit: VecDeque::new(),
+                audit_capacity: capacity,
+                next_audit_id: 1,
+            })),
+        }
+    }
+
+    pub async fn count(&self) -> usize {
+        self.inner.read().await.users.len()
+    }
+}
+
+impl Default for InMemoryUserStore {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl UserStore for InMemoryUserStore {
+    async fn create_user(
+        &self,
+        input: &CreateUserRequest,
+        ctx: &AuditContext,
+    ) -> Result<(User, UserEvent), StoreError> {
+        let mut inner = self.inner.write().await;
+        inner.check_email(&input.email, None)?;
+        let user = inner.insert(input);
+        let after = snapshot(&user, None);
+        inner.record(
+            ctx,
+            AuditRecord::new(user.id, AuditAction::Create, None, Some(after)),
+        );
+        let event = UserEvent::with_user(UserEventType::Created, &user);
+        Ok((user, event))
+    }
+
+    async fn create_users(
+        &self,
+        inputs: &[CreateUserRequest],
+        ctx: &AuditContext,
+    ) -> Result<Vec<CreateOutcome>, StoreError> {
+        let mut inner = self.inner.write().await;
+        // 整批共用一份邮箱集合，避免逐条扫描全部用户
+        let mut emails: HashSet<String> = inner
+            .users
+            .values()
+            .map(|user| user.email.clone())
+            .collect();
+
+        let results = inputs
+            .iter()
+            .map(|input| {
+                if !emails.insert(input.email.clone()) {
+                    return Err(StoreError::Conflict("email".to_string()));
+                }
+                let user = inner.insert(input);
+                let after = snapshot(&user, None);
+                inner.record(
+                    ctx,
+                    AuditRecord::new(user.id, AuditAction::Create, None, Some(after)),
+                );
+                let event = UserEvent::with_user(UserEventType::Created, &user);
+                Ok((user, event))
+            })
+            .collect();
+        Ok(results)
+    }
+
+    async fn get_user(&self, id: i32) -> Result<Option<User>, StoreError> {
+        let inner = self.inner.read().await;
+        Ok(inner.users.get(&id).cloned())
+    }
+
+    async fn list_users(&self, query: &UserQuery) -> Result<Vec<User>, StoreError> {
+        let inner = self.inner.read().await;
+        let mut users: Vec<User> = inner
+            .users
+            .values()
+            .filter(|user| query.filter.matches(user) && query.is_after_cursor(user))
+            .cloned()
+            .collect();
+        users.sort_by(|a, b| query.compare(a, b));
+        users.truncate(query.limit as usize);
+        Ok(users)
+    }
+
+    async fn replace_user(
+        &self,
+        id: i32,
+        input: &CreateUserRequest,
+        precondition: &Precondition,
+        ctx: &AuditContext,
+    ) -> Result<Option<(User, UserEvent)>, StoreError> {
+        let mut inner = self.inner.write().await;
+        let before = match inner.users.get(&id) {
+            None => return Ok(None),
+            Some(user) if !precondition.allows(user.version) => {
+                return Err(StoreError::VersionMismatch)
+            }
+            Some(user) => snapshot(user, None),
+        };
+        inner.check_email(&input.email, Some(id))?;
+
+        let Some(user) = inner.users.get_mut(&id) else {
+            return Ok(None);
+        };
+        user.name = input.name.clone();
+        user.email = input.email.clone();
+        user.updated_at = now();
+        user.version += 1;
+        let user = user.clone();
+
+        let after = snapshot(&user, None);
+        inner.record(
+            ctx,
+            AuditRecord::new(id, AuditAction::Replace, Some(before), Some(after)),
+        );
+        let event = UserEvent::with_user(UserEventType::Updated, &user);
+        Ok(Some((user, event)))
+    }
+
+    async fn delete_user(
+        &self,
+        id: i32,
+        precondition: &Precondition,
+        ctx: &AuditContext,
+    ) -> Result<Option<UserEvent>, StoreError> {
+        let mut inner = self.inner.write().await;
+        match inner.users.get(&id) {
+            None => return Ok(None),
+            Some(user) if !precondition.allows(user.version) => {
+                return Err(StoreError::VersionMismatch)
+            }
+            Some(_) => {}
+        }
+
+        let Some(mut user) = inner.users.remove(&id) else {
+            return Ok(None);
+        };
+        user.version += 1;
+        let deleted_at = now();
+        let record = AuditRecord::new(
+            id,
+            AuditAction::Delete,
+            Some(snapshot(&user, None)),
+            Some(snapshot(&user, Some(deleted_at))),
+        );
+        inner.deleted.insert(id, (user, deleted_at));
+        inner.record(ctx, record);
+        Ok(Some(UserEvent::with_id(UserEventType::Deleted, id)))
+    }
+
+    async fn restore_user(
+        &self,
+        id: i32,
+        ctx: &AuditContext,
+    ) -> Result<Option<(User, UserEvent)>, StoreError> {
+        let mut inner = self.inner.write().await;
+        let Some((user, _)) = inner.deleted.get(&id) else {
+            return Ok(None);
+        };
+        inner.check_email(&user.email, None)?;
+
+        let Some((mut user, deleted_at)) = inner.deleted.remove(&id) else {
+            return Ok(None);
+        };
+        let before = snapshot(&user, Some(deleted_at));
+        user.updated_at = now();
+        user.version += 1;
+        inner.users.insert(id, user.clone());
+
+        let after = snapshot(&user, None);
+        inner.record(
+            ctx,
+            AuditRecord::new(id, AuditAction::Restore, Some(before), Some(after)),
+        );
+        let event = UserEvent::with_user(UserEventType::Restored, &user);
+        Ok(Some((user, event)))
+    }
+
+    async fn purge_deleted(
+        &self,
+        deleted_before: DateTime<Utc>,
+        ctx: &AuditContext,
+    ) -> Result<u64, StoreError> {
+        let mut inner = self.inner.write().await;
+        let expired: Vec<i32> = inner
+            .deleted
+            .iter()
+            .filter(|(_, (_, deleted_at))| *deleted_at < deleted_before)
+            .map(|(id, _)| *id)
+            .collect();
+
+        for id in &expired {
+            if let Some((user, deleted_at)) = inner.deleted.remove(id) {
+                let before = snapshot(&user, Some(deleted_at));
+                inner.record(
+                    ctx,
+                    AuditRecord::new(*id, AuditAction::Purge, Some(before), None),
+                );
+            }
+        }
+        Ok(expired.len() as u64)
+    }
+
+    async fn user_history(
+        &self,
+        user_id: i32,
+        query: &HistoryQuery,
+    ) -> Result<Vec<AuditEntry>, StoreError> {
+        let inner = self.inner.read().await;
+        let entries = inner
+            .audit
+            .iter()
+            .rev()
+            .filter(|entry| entry.user_id == user_id)
+            .filter(|entry| query.before_id.is_none_or(|before_id| entry.id < before_id))
+            .take(query.limit as usize)
+            .cloned()
+            .collect();
+        Ok(entries)
+    }
+}
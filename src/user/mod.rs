@@ -0,0 +1,43 @@
+mod audit;
+mod bulk;
+mod etag;
+mod events;
+mod memory;
+mod model;
+mod openapi;
+mod outbox;
+mod patch;
+mod postgres;
+mod query;
+mod retry;
+mod router;
+mod state;
+mod store;
+mod stream;
+mod unit_of_work;
+
+pub use audit::{
+    snapshot, AuditAction, AuditContext, AuditEntry, AuditRecord, HistoryParams, HistoryQuery,
+    DEFAULT_HISTORY_LIMIT, MAX_HISTORY_LIMIT,
+};
+pub use bulk::{
+    export_stream, import, ExportFormat, ExportParams, ImportReport, ImportRow, ImportRows,
+    RowResult, EXPORT_PAGE_SIZE, IMPORT_BATCH_SIZE, MAX_IMPORT_ROWS,
+};
+pub use etag::{etag, IfMatch};
+pub use events::{StreamEvent, Subscription, UserEvents, DEFAULT_REPLAY_CAPACITY};
+pub use memory::{InMemoryUserStore, DEFAULT_AUDIT_CAPACITY};
+pub use model::{CreateUserRequest, User};
+pub use openapi::{docs_router, openapi};
+pub use outbox::{UserEvent, UserEventType};
+pub use patch::{UserPatch, JSON_PATCH, MERGE_PATCH};
+pub use postgres::UserRepository;
+pub use query::{
+    Cursor, ListUsersParams, Page, Sort, SortField, SortKey, UserFilter, UserQuery, DEFAULT_LIMIT,
+    MAX_LIMIT,
+};
+pub use retry::{retry, RetryPolicy};
+pub use router::{admin_router, user_router, ADMIN_ROLE};
+pub use state::AppState;
+pub use store::{CreateOutcome, Precondition, StoreError, UserStore};
+pub use unit_of_work::{PooledSession, UnitOfWork, UserSession};
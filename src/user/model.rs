@@ -0,0 +1,68 @@
+use std::fmt;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Deserializer, Serialize};
+use sqlx::FromRow;
+use utoipa::ToSchema;
+use validator::Validate;
+
+// 数据模型，也是接口的输出格式；id 和时间戳只由服务端生成，时间以 RFC 3339 输出
+#[derive(Clone, Serialize, FromRow, ToSchema)]
+pub struct User {
+    pub id: i32,
+    pub name: String,
+    pub email: String,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    // 每次更新递增，用作 ETag
+    pub version: i32,
+}
+
+// 请求类型，创建（POST）和整体替换（PUT）共用；长度上限与 users 表的 VARCHAR(255) 保持一致。
+// 缺失的字段由 extract::from_value 报告为必填，serde(default) 只用于同时校验其余字段，
+// 文档中仍标为必填且不带默认值
+#[derive(Default, Deserialize, Validate, ToSchema)]
+#[serde(default, deny_unknown_fields)]
+pub struct CreateUserRequest {
+    #[serde(deserialize_with = "trimmed")]
+    #[validate(length(min = 1, max = 255, message = "must be between 1 and 255 characters"))]
+    #[schema(required = true, default, min_length = 1, max_length = 255)]
+    pub name: String,
+    #[schema(required = true, default, format = Email, max_length = 255)]
+    #[validate(
+        email(message = "must be a valid email address"),
+        length(max = 255, message = "must be at most 255 characters")
+    )]
+    pub email: String,
+}
+
+// Debug 输出隐藏姓名和邮箱，避免个人信息进入日志
+const REDACTED: &str = "[REDACTED]";
+
+impl fmt::Debug for User {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("User")
+            .field("id", &self.id)
+            .field("name", &REDACTED)
+            .field("email", &REDACTED)
+            .field("created_at", &self.created_at)
+            .field("updated_at", &self.updated_at)
+            .field("version", &self.version)
+            .finish()
+    }
+}
+
+impl fmt::Debug for CreateUserRequest {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("CreateUserRequest")
+            .field("name", &REDACTED)
+            .field("email", &REDACTED)
+            .finish()
+    }
+}
+
+// 姓名去除首尾空白后再校验长度
+fn trimmed<'de, D: Deserializer<'de>>(deserializer: D) -> Result<String, D::Error> {
+    let value = String::deserialize(deserializer)?;
+    Ok(value.trim().to_string())
+}
@@ -0,0 +1,105 @@
+use axum::Router;
+use utoipa::{
+    openapi::{
+        security::{HttpAuthScheme, HttpBuilder, SecurityScheme},
+        OpenApi as OpenApiDoc, RefOr, Response, ResponseBuilder,
+    },
+    Modify, OpenApi,
+};
+use utoipa_swagger_ui::SwaggerUi;
+
+use super::{router, stream};
+
+// 由路由处理函数上的 #[utoipa::path] 和类型上的 ToSchema 生成，
+// 仓库根目录的 openapi.json 由 tests/openapi.rs 保证与此一致
+#[derive(OpenApi)]
+#[openapi(
+    info(title = "hello-rust", description = "用户管理 API"),
+    paths(
+        router::get_users,
+        router::create_user,
+        router::import_users,
+        router::export_users,
+        stream::sse_events,
+        stream::ws_events,
+        router::get_user,
+        router::replace_user,
+        router::patch_user,
+        router::delete_user,
+        router::restore_user,
+        router::get_user_history,
+        router::purge_users,
+    ),
+    components(schemas(super::StreamEvent)),
+    tags(
+        (name = "users", description = "用户的增删改查、批量导入导出和变更推送"),
+        (name = "admin", description = "管理接口，需要 admin 角色"),
+    ),
+    modifiers(&Common),
+)]
+struct ApiDoc;
+
+// 所有接口共有的部分：Bearer 认证方案，以及限流、过载和超时时的错误响应
+struct Common;
+
+impl Modify for Common {
+    fn modify(&self, openapi: &mut OpenApiDoc) {
+        // Cargo.toml 未声明 license，不输出空的 license 对象
+        openapi.info.license = None;
+
+        let components = openapi.components.get_or_insert_with(Default::default);
+        components.add_security_scheme(
+            "bearer",
+            SecurityScheme::Http(
+                HttpBuilder::new()
+                    .scheme(HttpAuthScheme::Bearer)
+                    .bearer_format("JWT")
+                    .build(),
+            ),
+        );
+
+        let common = [
+            ("429", "超出限流配额，Retry-After 为建议的重试间隔"),
+            ("503", "服务繁忙或正在关闭"),
+            ("504", "请求处理超时"),
+        ];
+        for item in openapi.paths.paths.values_mut() {
+            let operations = [
+                &mut item.get,
+                &mut item.post,
+                &mut item.put,
+                &mut item.patch,
+                &mut item.delete,
+            ];
+            for operation in operations.into_iter().flatten() {
+                for (status, description) in common {
+                    operation
+                        .responses
+                        .responses
+                        .entry(status.to_string())
+                        .or_insert_with(|| error_response(description));
+                }
+            }
+        }
+    }
+}
+
+fn error_response(description: &str) -> RefOr<Response> {
+    ResponseBuilder::new()
+        .description(description)
+        .content(
+            "application/json",
+            utoipa::openapi::Content::new(Some(utoipa::openapi::Ref::from_schema_name("Error"))),
+        )
+        .into()
+}
+
+// OpenAPI 3.1 文档
+pub fn openapi() -> OpenApiDoc {
+    ApiDoc::openapi()
+}
+
+// GET /openapi.json 和 /docs 下的 Swagger UI
+pub fn docs_router() -> Router {
+    SwaggerUi::new("/docs").url("/openapi.json", openapi()).into()
+}
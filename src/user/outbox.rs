@@ -0,0 +1,113 @@
+use chrono::{DateTime, SubsecRound, Utc};
+use serde::Serialize;
+use serde_json::{json, Value};
+use sqlx::PgConnection;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use super::model::User;
+
+// 下游系统订阅的事件类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ToSchema)]
+pub enum UserEventType {
+    #[serde(rename = "user.created")]
+    Created,
+    #[serde(rename = "user.updated")]
+    Updated,
+    #[serde(rename = "user.deleted")]
+    Deleted,
+    #[serde(rename = "user.restored")]
+    Restored,
+    #[serde(rename = "user.purged")]
+    Purged,
+}
+
+impl UserEventType {
+    pub const ALL: [UserEventType; 5] = [
+        UserEventType::Created,
+        UserEventType::Updated,
+        UserEventType::Deleted,
+        UserEventType::Restored,
+        UserEventType::Purged,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            UserEventType::Created => "user.created",
+            UserEventType::Updated => "user.updated",
+            UserEventType::Deleted => "user.deleted",
+            UserEventType::Restored => "user.restored",
+            UserEventType::Purged => "user.purged",
+        }
+    }
+}
+
+// 领域事件，payload 即 webhook 的请求体
+#[derive(Debug, Clone)]
+pub struct UserEvent {
+    pub id: Uuid,
+    pub event_type: UserEventType,
+    pub user_id: i32,
+    pub occurred_at: DateTime<Utc>,
+    pub data: Value,
+}
+
+impl UserEvent {
+    fn new(event_type: UserEventType, user_id: i32, data: Value) -> Self {
+        UserEvent {
+            id: Uuid::new_v4(),
+            event_type,
+            user_id,
+            occurred_at: Utc::now().trunc_subsecs(6),
+            data,
+        }
+    }
+
+    // 创建、更新、恢复事件携带变更后的用户
+    pub fn with_user(event_type: UserEventType, user: &User) -> Self {
+        let data = serde_json::to_value(user).expect("user is always serializable");
+        Self::new(event_type, user.id, data)
+    }
+
+    // 删除、清除事件只携带用户 id
+    pub fn with_id(event_type: UserEventType, user_id: i32) -> Self {
+        Self::new(event_type, user_id, json!({ "id": user_id }))
+    }
+
+    pub fn payload(&self) -> Value {
+        json!({
+            "id": self.id,
+            "type": self.event_type.as_str(),
+            "occurred_at": self.occurred_at,
+            "data": self.data,
+        })
+    }
+}
+
+// 在调用方的事务中写入 outbox，由 webhook 分发器异步投递
+pub(super) async fn write_events(
+    conn: &mut PgConnection,
+    events: &[UserEvent],
+) -> Result<(), sqlx::Error> {
+    if events.is_empty() {
+        return Ok(());
+    }
+    let ids: Vec<Uuid> = events.iter().map(|event| event.id).collect();
+    let types: Vec<&str> = events.iter().map(|event| event.event_type.as_str()).collect();
+    let user_ids: Vec<i32> = events.iter().map(|event| event.user_id).collect();
+    let payloads: Vec<Value> = events.iter().map(UserEvent::payload).collect();
+
+    sqlx::query(
+        r#"
+        INSERT INTO user_outbox (event_id, event_type, user_id, payload)
+        SELECT * FROM UNNEST($1::UUID[], $2::TEXT[], $3::INTEGER[], $4::JSONB[])
+        "#,
+    )
+    .bind(&ids)
+    .bind(&types)
+    .bind(&user_ids)
+    .bind(&payloads)
+    .execute(conn)
+    .await?;
+    Ok(())
+}
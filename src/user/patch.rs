@@ -0,0 +1,79 @@
+use axum::{
+    extract::{FromRequest, Request},
+    http::StatusCode,
+};
+use serde_json::Value;
+
+use super::model::{CreateUserRequest, User};
+use crate::error::ApiError;
+use crate::extract::{from_value, typed_body};
+
+pub const MERGE_PATCH: &str = "application/merge-patch+json";
+pub const JSON_PATCH: &str = "application/json-patch+json";
+
+// PATCH /api/users/{id} 的请求体，按 Content-Type 区分
+pub enum UserPatch {
+    // RFC 7396，application/json 也按合并补丁处理
+    Merge(Value),
+    // RFC 6902
+    Json(json_patch::Patch),
+}
+
+impl UserPatch {
+    // 在用户当前可修改字段上应用补丁，得到完整的替换内容并校验；
+    // 合并补丁中将字段设为 null 表示删除该字段，因此会因缺少必填字段而失败
+    pub fn apply(&self, user: &User) -> Result<CreateUserRequest, ApiError> {
+        let mut document = serde_json::json!({
+            "name": user.name,
+            "email": user.email,
+        });
+
+        match self {
+            UserPatch::Merge(patch) => json_patch::merge(&mut document, patch),
+            UserPatch::Json(patch) => {
+                json_patch::patch(&mut document, patch).map_err(|err| ApiError::Rejected {
+                    status: StatusCode::UNPROCESSABLE_ENTITY,
+                    message: format!("Failed to apply JSON Patch: {}", err),
+                })?
+            }
+        }
+
+        from_value(document)
+    }
+}
+
+fn invalid_body(err: serde_json::Error) -> ApiError {
+    ApiError::Rejected {
+        status: StatusCode::BAD_REQUEST,
+        message: format!("Failed to parse the patch document: {}", err),
+    }
+}
+
+impl<S: Send + Sync> FromRequest<S> for UserPatch {
+    type Rejection = ApiError;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let (content_type, body) = typed_body(req, state).await?;
+        match content_type.as_str() {
+            MERGE_PATCH | "application/json" => {
+                let patch: Value = serde_json::from_slice(&body).map_err(invalid_body)?;
+                // 合并补丁的顶层必须是对象，否则会整体替换文档
+                if !patch.is_object() {
+                    return Err(ApiError::Rejected {
+                        status: StatusCode::UNPROCESSABLE_ENTITY,
+                        message: "Merge patch must be a JSON object".to_string(),
+                    });
+                }
+                Ok(UserPatch::Merge(patch))
+            }
+            JSON_PATCH => {
+                let patch = serde_json::from_slice(&body).map_err(invalid_body)?;
+                Ok(UserPatch::Json(patch))
+            }
+            _ => Err(ApiError::Rejected {
+                status: StatusCode::UNSUPPORTED_MEDIA_TYPE,
+                message: format!("Expected Content-Type {} or {}", MERGE_PATCH, JSON_PATCH),
+            }),
+        }
+    }
+}
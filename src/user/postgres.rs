@@ -0,0 +1,154 @@
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+
+use super::audit::{AuditContext, AuditEntry, HistoryQuery};
+use super::model::{CreateUserRequest, User};
+use super::outbox::UserEvent;
+use super::query::UserQuery;
+use super::retry::{retry, RetryPolicy};
+use super::store::{CreateOutcome, Precondition, StoreError, UserStore};
+use super::unit_of_work::{PooledSession, UnitOfWork, UserSession};
+use crate::metrics;
+
+// 写事务的隔离级别
+const BEGIN: &str = "BEGIN ISOLATION LEVEL REPEATABLE READ";
+
+// 数据库操作
+#[derive(Clone)]
+pub struct UserRepository {
+    pool: PgPool,
+    retry: RetryPolicy,
+}
+
+impl UserRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self {
+            pool,
+            retry: RetryPolicy::default(),
+        }
+    }
+
+    pub fn with_retry_policy(mut self, policy: RetryPolicy) -> Self {
+        self.retry = policy;
+        self
+    }
+
+    // 开启 REPEATABLE READ 事务，需显式 commit，否则在 drop 时回滚。
+    // 并发修改同一行时后提交的一方得到序列化失败（40001），由 retry 重新执行整个事务
+    pub async fn begin(&self) -> Result<UnitOfWork, StoreError> {
+        let tx = metrics::acquire(self.pool.begin_with(BEGIN)).await?;
+        Ok(UserSession::new(tx))
+    }
+
+    // 借出一个普通连接，只用于读操作
+    pub async fn session(&self) -> Result<PooledSession, StoreError> {
+        let conn = metrics::acquire(self.pool.acquire()).await?;
+        Ok(UserSession::new(conn))
+    }
+}
+
+// 每个写操作在独立事务中执行，序列化失败或死锁时整体重试
+impl UserStore for UserRepository {
+    async fn create_user(
+        &self,
+        input: &CreateUserRequest,
+        ctx: &AuditContext,
+    ) -> Result<(User, UserEvent), StoreError> {
+        retry(&self.retry, || async {
+            let mut uow = self.begin().await?;
+            let created = uow.create_user(input, ctx).await?;
+            uow.commit().await?;
+            Ok(created)
+        })
+        .await
+    }
+
+    async fn create_users(
+        &self,
+        inputs: &[CreateUserRequest],
+        ctx: &AuditContext,
+    ) -> Result<Vec<CreateOutcome>, StoreError> {
+        retry(&self.retry, || async {
+            let mut uow = self.begin().await?;
+            let results = uow.create_users(inputs, ctx).await?;
+            uow.commit().await?;
+            Ok(results)
+        })
+        .await
+    }
+
+    async fn get_user(&self, id: i32) -> Result<Option<User>, StoreError> {
+        self.session().await?.get_user(id).await
+    }
+
+    async fn list_users(&self, query: &UserQuery) -> Result<Vec<User>, StoreError> {
+        self.session().await?.list_users(query).await
+    }
+
+    async fn replace_user(
+        &self,
+        id: i32,
+        input: &CreateUserRequest,
+        precondition: &Precondition,
+        ctx: &AuditContext,
+    ) -> Result<Option<(User, UserEvent)>, StoreError> {
+        retry(&self.retry, || async {
+            let mut uow = self.begin().await?;
+            let replaced = uow.replace_user(id, input, precondition, ctx).await?;
+            uow.commit().await?;
+            Ok(replaced)
+        })
+        .await
+    }
+
+    async fn delete_user(
+        &self,
+        id: i32,
+        precondition: &Precondition,
+        ctx: &AuditContext,
+    ) -> Result<Option<UserEvent>, StoreError> {
+        retry(&self.retry, || async {
+            let mut uow = self.begin().await?;
+            let deleted = uow.delete_user(id, precondition, ctx).await?;
+            uow.commit().await?;
+            Ok(deleted)
+        })
+        .await
+    }
+
+    async fn restore_user(
+        &self,
+        id: i32,
+        ctx: &AuditContext,
+    ) -> Result<Option<(User, UserEvent)>, StoreError> {
+        retry(&self.retry, || async {
+            let mut uow = self.begin().await?;
+            let restored = uow.restore_user(id, ctx).await?;
+            uow.commit().await?;
+            Ok(restored)
+        })
+        .await
+    }
+
+    async fn purge_deleted(
+        &self,
+        deleted_before: DateTime<Utc>,
+        ctx: &AuditContext,
+    ) -> Result<u64, StoreError> {
+        retry(&self.retry, || async {
+            let mut uow = self.begin().await?;
+            let purged = uow.purge_deleted(deleted_before, ctx).await?;
+            uow.commit().await?;
+            Ok(purged)
+        })
+        .await
+    }
+
+    async fn user_history(
+        &self,
+        user_id: i32,
+        query: &HistoryQuery,
+    ) -> Result<Vec<AuditEntry>, StoreError> {
+        self.session().await?.user_history(user_id, query).await
+    }
+}
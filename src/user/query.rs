@@ -0,0 +1,280 @@
+use std::{cmp::Ordering, fmt, str::FromStr};
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+
+use super::model::User;
+use crate::error::{ApiError, FieldError};
+
+pub const DEFAULT_LIMIT: u32 = 50;
+pub const MAX_LIMIT: u32 = 200;
+
+// 排序字段，id 始终作为最后的排序键保证顺序稳定
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortField {
+    Id,
+    Name,
+    CreatedAt,
+}
+
+// 排序方式，格式为 "name" 或 "-name"（倒序）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Sort {
+    pub field: SortField,
+    pub descending: bool,
+}
+
+impl Default for Sort {
+    fn default() -> Self {
+        Sort {
+            field: SortField::CreatedAt,
+            descending: true,
+        }
+    }
+}
+
+impl FromStr for Sort {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (descending, name) = match s.strip_prefix('-') {
+            Some(name) => (true, name),
+            None => (false, s),
+        };
+        let field = match name {
+            "id" => SortField::Id,
+            "name" => SortField::Name,
+            "created_at" => SortField::CreatedAt,
+            _ => return Err(format!("unknown sort field: {}", name)),
+        };
+        Ok(Sort { field, descending })
+    }
+}
+
+impl fmt::Display for Sort {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self.field {
+            SortField::Id => "id",
+            SortField::Name => "name",
+            SortField::CreatedAt => "created_at",
+        };
+        if self.descending {
+            write!(f, "-{}", name)
+        } else {
+            write!(f, "{}", name)
+        }
+    }
+}
+
+// 游标中记录的上一页最后一条的排序键
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "field", content = "value", rename_all = "snake_case")]
+pub enum SortKey {
+    Id,
+    Name(String),
+    CreatedAt(DateTime<Utc>),
+}
+
+// 键集分页游标，编码为 base64url(JSON)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Cursor {
+    pub sort: String,
+    pub key: SortKey,
+    pub id: i32,
+}
+
+impl Cursor {
+    pub(super) fn for_user(sort: Sort, user: &User) -> Self {
+        let key = match sort.field {
+            SortField::Id => SortKey::Id,
+            SortField::Name => SortKey::Name(user.name.clone()),
+            SortField::CreatedAt => SortKey::CreatedAt(user.created_at),
+        };
+        Cursor {
+            sort: sort.to_string(),
+            key,
+            id: user.id,
+        }
+    }
+
+    pub fn encode(&self) -> String {
+        let json = serde_json::to_vec(self).expect("cursor is always serializable");
+        URL_SAFE_NO_PAD.encode(json)
+    }
+
+    // 游标必须与当前请求的排序方式一致
+    pub fn decode(s: &str, sort: Sort) -> Option<Self> {
+        let json = URL_SAFE_NO_PAD.decode(s).ok()?;
+        let cursor: Cursor = serde_json::from_slice(&json).ok()?;
+        let matches = matches!(
+            (sort.field, &cursor.key),
+            (SortField::Id, SortKey::Id)
+                | (SortField::Name, SortKey::Name(_))
+                | (SortField::CreatedAt, SortKey::CreatedAt(_))
+        );
+        (matches && cursor.sort == sort.to_string()).then_some(cursor)
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct UserFilter {
+    pub name_prefix: Option<String>,
+    // 邮箱域名，大小写不敏感
+    pub email_domain: Option<String>,
+}
+
+impl UserFilter {
+    pub fn matches(&self, user: &User) -> bool {
+        if let Some(prefix) = &self.name_prefix {
+            if !user.name.starts_with(prefix.as_str()) {
+                return false;
+            }
+        }
+        if let Some(domain) = &self.email_domain {
+            let user_domain = user.email.rsplit_once('@').map(|(_, domain)| domain);
+            if user_domain.map(str::to_lowercase) != Some(domain.to_lowercase()) {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+// 列表查询条件，存储实现按此返回最多 limit 条
+#[derive(Debug, Clone)]
+pub struct UserQuery {
+    pub filter: UserFilter,
+    pub sort: Sort,
+    pub after: Option<Cursor>,
+    pub limit: u32,
+}
+
+impl UserQuery {
+    // 按 (排序键, id) 比较，字符串按字节序，与 Postgres 的 COLLATE "C" 一致
+    pub fn compare(&self, a: &User, b: &User) -> Ordering {
+        let ordering = match self.sort.field {
+            SortField::Id => Ordering::Equal,
+            SortField::Name => a.name.cmp(&b.name),
+            SortField::CreatedAt => a.created_at.cmp(&b.created_at),
+        }
+        .then(a.id.cmp(&b.id));
+        if self.sort.descending {
+            ordering.reverse()
+        } else {
+            ordering
+        }
+    }
+
+    pub fn is_after_cursor(&self, user: &User) -> bool {
+        let Some(cursor) = &self.after else {
+            return true;
+        };
+        let ordering = match &cursor.key {
+            SortKey::Id => Ordering::Equal,
+            SortKey::Name(name) => user.name.as_str().cmp(name.as_str()),
+            SortKey::CreatedAt(created_at) => user.created_at.cmp(created_at),
+        }
+        .then(user.id.cmp(&cursor.id));
+        if self.sort.descending {
+            ordering == Ordering::Less
+        } else {
+            ordering == Ordering::Greater
+        }
+    }
+}
+
+// GET /api/users 的查询参数
+#[derive(Debug, Default, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ListUsersParams {
+    #[param(minimum = 1, maximum = 200, default = 50)]
+    pub limit: Option<u32>,
+    /// 上一页响应中的 next_cursor
+    pub after: Option<String>,
+    /// id、name 或 created_at，前缀 - 表示倒序
+    #[param(example = "-created_at")]
+    pub sort: Option<String>,
+    /// 名称前缀，区分大小写
+    pub name_prefix: Option<String>,
+    /// 邮箱域名，不区分大小写，如 example.com
+    pub email_domain: Option<String>,
+}
+
+impl ListUsersParams {
+    pub fn into_query(self) -> Result<UserQuery, ApiError> {
+        let mut errors = Vec::new();
+
+        let limit = self.limit.unwrap_or(DEFAULT_LIMIT);
+        if !(1..=MAX_LIMIT).contains(&limit) {
+            errors.push(FieldError::new(
+                "limit",
+                "range",
+                format!("must be between 1 and {}", MAX_LIMIT),
+            ));
+        }
+
+        let sort = match self.sort.as_deref().map(Sort::from_str) {
+            None => Sort::default(),
+            Some(Ok(sort)) => sort,
+            Some(Err(message)) => {
+                errors.push(FieldError::new("sort", "invalid", message));
+                Sort::default()
+            }
+        };
+
+        let after = match &self.after {
+            None => None,
+            Some(raw) => {
+                let cursor = Cursor::decode(raw, sort);
+                if cursor.is_none() {
+                    errors.push(FieldError::new(
+                        "after",
+                        "invalid_cursor",
+                        "is not a valid cursor for this sort order",
+                    ));
+                }
+                cursor
+            }
+        };
+
+        if !errors.is_empty() {
+            return Err(ApiError::Validation(errors));
+        }
+
+        Ok(UserQuery {
+            filter: UserFilter {
+                name_prefix: self.name_prefix.filter(|prefix| !prefix.is_empty()),
+                email_domain: self.email_domain.filter(|domain| !domain.is_empty()),
+            },
+            sort,
+            after,
+            limit,
+        })
+    }
+}
+
+// 分页响应
+#[derive(Debug, Serialize, ToSchema)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    pub next_cursor: Option<String>,
+}
+
+impl Page<User> {
+    // rows 需按 query 查询 limit + 1 条，多出的一条表示还有下一页
+    pub fn from_rows(mut rows: Vec<User>, query: &UserQuery) -> Self {
+        let limit = query.limit as usize;
+        let next_cursor = if rows.len() > limit {
+            rows.truncate(limit);
+            rows.last().map(|user| Cursor::for_user(query.sort, user).encode())
+        } else {
+            None
+        };
+        Page {
+            items: rows,
+            next_cursor,
+        }
+    }
+}
@@ -0,0 +1,60 @@
+use std::{future::Future, time::Duration};
+
+use rand::Rng;
+
+use super::store::StoreError;
+
+// 事务重试策略，退避时间按指数增长并加入随机抖动
+#[derive(Debug, Clone, Copy)]
+pub struct RetryPolicy {
+    // 包含首次执行在内的最大尝试次数
+    pub max_attempts: u32,
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            max_attempts: 3,
+            base_delay: Duration::from_millis(20),
+            max_delay: Duration::from_secs(1),
+        }
+    }
+}
+
+impl RetryPolicy {
+    // 第 attempt 次失败后的等待时间，在 [d/2, d] 之间随机取值
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let factor = 2u32.saturating_pow(attempt.saturating_sub(1));
+        let delay = self.base_delay.saturating_mul(factor).min(self.max_delay);
+        let half = delay / 2;
+        half + half.mul_f64(rand::rng().random::<f64>())
+    }
+}
+
+// 执行 op，遇到序列化失败或死锁时按策略重试；op 每次都需要开启新的事务
+pub async fn retry<T, F, Fut>(policy: &RetryPolicy, mut op: F) -> Result<T, StoreError>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, StoreError>>,
+{
+    let mut attempt = 1;
+    loop {
+        match op().await {
+            Err(err) if err.is_retryable() && attempt < policy.max_attempts => {
+                let delay = policy.backoff(attempt);
+                tracing::warn!(
+                    attempt,
+                    delay_ms = delay.as_millis() as u64,
+                    error = %err,
+                    "retrying transaction"
+                );
+                metrics::counter!("db_transaction_retries_total").increment(1);
+                tokio::time::sleep(delay).await;
+                attempt += 1;
+            }
+            result => return result,
+        }
+    }
+}
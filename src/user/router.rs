@@ -0,0 +1,421 @@
+use axum::{
+    extract::{DefaultBodyLimit, State},
+    http::{HeaderName, HeaderValue, StatusCode},
+    response::Response,
+    routing::{get, post},
+    Router,
+};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use utoipa::ToSchema;
+
+use super::audit::{AuditContext, AuditEntry, HistoryParams, HistoryQuery};
+use super::bulk::{self, ExportParams, ImportReport, ImportRows, IMPORT_BODY_LIMIT};
+use super::etag::{etag_header, IfMatch};
+use super::model::{CreateUserRequest, User};
+use super::patch::UserPatch;
+use super::query::{ListUsersParams, Page, UserQuery};
+use super::state::AppState;
+use super::store::{Precondition, StoreError, UserStore};
+use super::stream::{sse_events, ws_events};
+use crate::auth::Principal;
+use crate::error::ApiError;
+use crate::extract::{Json, Path, Query, ValidJson};
+
+pub const ADMIN_ROLE: &str = "admin";
+
+// 单个用户的响应，带 ETag 响应头
+type TaggedUser = ([(HeaderName, HeaderValue); 1], Json<User>);
+
+fn tagged(user: User) -> TaggedUser {
+    (etag_header(&user), Json(user))
+}
+
+fn user_not_found(id: i32) -> ApiError {
+    ApiError::NotFound(format!("User {} not found", id))
+}
+
+// 路由处理函数
+#[utoipa::path(
+    get,
+    path = "/api/users",
+    tag = "users",
+    params(ListUsersParams),
+    responses(
+        (status = 200, description = "按 sort 排序的一页用户", body = Page<User>),
+        (status = 422, description = "查询参数不合法", body = ApiError),
+    )
+)]
+pub(super) async fn get_users<S: UserStore>(
+    State(state): State<AppState<S>>,
+    Query(params): Query<ListUsersParams>,
+) -> Result<Json<Page<User>>, ApiError> {
+    let query = params.into_query()?;
+
+    // 多取一条用于判断是否还有下一页
+    let fetch = UserQuery {
+        limit: query.limit + 1,
+        ..query.clone()
+    };
+    let rows = state.store.list_users(&fetch).await?;
+
+    Ok(Json(Page::from_rows(rows, &query)))
+}
+
+// 批量导入，逐行返回结果
+#[utoipa::path(
+    post,
+    path = "/api/users:bulk",
+    tag = "users",
+    request_body(
+        description = "每行一个 CreateUserRequest 的 NDJSON，或含 name、email 列的 CSV",
+        content(
+            (String = "application/x-ndjson"),
+            (String = "text/csv"),
+        )
+    ),
+    responses(
+        (status = 200, description = "逐行的导入结果", body = ImportReport),
+        (status = 413, description = "请求体或行数超出上限", body = ApiError),
+        (status = 415, description = "不支持的 Content-Type", body = ApiError),
+        (status = 422, description = "CSV 缺少必需的列", body = ApiError),
+    )
+)]
+pub(super) async fn import_users<S: UserStore>(
+    State(state): State<AppState<S>>,
+    ctx: AuditContext,
+    ImportRows(rows): ImportRows,
+) -> Json<ImportReport> {
+    Json(bulk::import(&state.store, rows, &ctx, &state.events).await)
+}
+
+// 流式导出全部（或按条件过滤的）用户
+#[utoipa::path(
+    get,
+    path = "/api/users:export",
+    tag = "users",
+    params(ExportParams),
+    responses(
+        (
+            status = 200,
+            description = "按 id 顺序流式导出的用户",
+            content(
+                (String = "application/x-ndjson"),
+                (String = "text/csv"),
+            )
+        ),
+    )
+)]
+pub(super) async fn export_users<S: UserStore>(
+    State(state): State<AppState<S>>,
+    Query(params): Query<ExportParams>,
+) -> Response {
+    bulk::export_response(state.store, params)
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/users/{id}",
+    tag = "users",
+    params(("id" = i32, Path)),
+    responses(
+        (status = 200, body = User, headers(("ETag" = String))),
+        (status = 404, body = ApiError),
+    )
+)]
+pub(super) async fn get_user<S: UserStore>(
+    State(state): State<AppState<S>>,
+    Path(id): Path<i32>,
+) -> Result<TaggedUser, ApiError> {
+    let user = state
+        .store
+        .get_user(id)
+        .await?
+        .ok_or_else(|| user_not_found(id))?;
+
+    Ok(tagged(user))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/users",
+    tag = "users",
+    request_body = CreateUserRequest,
+    responses(
+        (status = 200, body = User, headers(("ETag" = String))),
+        (status = 409, description = "邮箱已被使用", body = ApiError),
+        (status = 422, body = ApiError),
+    )
+)]
+pub(super) async fn create_user<S: UserStore>(
+    State(state): State<AppState<S>>,
+    ctx: AuditContext,
+    ValidJson(payload): ValidJson<CreateUserRequest>,
+) -> Result<TaggedUser, ApiError> {
+    let (user, event) = state.store.create_user(&payload, &ctx).await?;
+    state.events.publish(event);
+    Ok(tagged(user))
+}
+
+// PUT 为整体替换，请求体须包含全部可修改字段
+#[utoipa::path(
+    put,
+    path = "/api/users/{id}",
+    tag = "users",
+    params(
+        ("id" = i32, Path),
+        ("If-Match" = Option<String>, Header, description = "之前响应中的 ETag，或 *"),
+    ),
+    request_body = CreateUserRequest,
+    responses(
+        (status = 200, body = User, headers(("ETag" = String))),
+        (status = 404, body = ApiError),
+        (status = 409, description = "邮箱已被使用", body = ApiError),
+        (status = 412, description = "If-Match 与当前版本不一致", body = ApiError),
+        (status = 422, body = ApiError),
+        (status = 428, description = "严格模式下未携带 If-Match", body = ApiError),
+    )
+)]
+pub(super) async fn replace_user<S: UserStore>(
+    State(state): State<AppState<S>>,
+    Path(id): Path<i32>,
+    if_match: IfMatch,
+    ctx: AuditContext,
+    ValidJson(payload): ValidJson<CreateUserRequest>,
+) -> Result<TaggedUser, ApiError> {
+    let precondition = if_match.into_precondition(state.require_if_match)?;
+    let (user, event) = state
+        .store
+        .replace_user(id, &payload, &precondition, &ctx)
+        .await?
+        .ok_or_else(|| user_not_found(id))?;
+    state.events.publish(event);
+
+    Ok(tagged(user))
+}
+
+// 并发修改导致补丁基线失效时的最大重试次数
+const PATCH_ATTEMPTS: usize = 3;
+
+// PATCH 在当前用户上应用补丁后整体替换，写入时以读取到的版本为条件，
+// 避免覆盖并发修改；客户端未指定具体版本时基线失效可重新读取并重试
+#[utoipa::path(
+    patch,
+    path = "/api/users/{id}",
+    tag = "users",
+    params(
+        ("id" = i32, Path),
+        ("If-Match" = Option<String>, Header, description = "之前响应中的 ETag，或 *"),
+    ),
+    request_body(
+        description = "JSON Merge Patch（RFC 7396）或 JSON Patch（RFC 6902）",
+        content(
+            (Object = "application/merge-patch+json"),
+            (Vec<Object> = "application/json-patch+json"),
+        )
+    ),
+    responses(
+        (status = 200, body = User, headers(("ETag" = String))),
+        (status = 404, body = ApiError),
+        (status = 409, description = "邮箱已被使用，或并发修改重试耗尽", body = ApiError),
+        (status = 412, description = "If-Match 与当前版本不一致", body = ApiError),
+        (status = 415, description = "不支持的 Content-Type", body = ApiError),
+        (status = 422, body = ApiError),
+        (status = 428, description = "严格模式下未携带 If-Match", body = ApiError),
+    )
+)]
+pub(super) async fn patch_user<S: UserStore>(
+    State(state): State<AppState<S>>,
+    Path(id): Path<i32>,
+    if_match: IfMatch,
+    ctx: AuditContext,
+    patch: UserPatch,
+) -> Result<TaggedUser, ApiError> {
+    let precondition = if_match.into_precondition(state.require_if_match)?;
+
+    for _ in 0..PATCH_ATTEMPTS {
+        let current = state
+            .store
+            .get_user(id)
+            .await?
+            .ok_or_else(|| user_not_found(id))?;
+        if !precondition.allows(current.version) {
+            return Err(StoreError::VersionMismatch.into());
+        }
+
+        let payload = patch.apply(&current)?;
+        let expected = Precondition::Versions(vec![current.version]);
+        match state.store.replace_user(id, &payload, &expected, &ctx).await {
+            Ok(Some((user, event))) => {
+                state.events.publish(event);
+                return Ok(tagged(user));
+            }
+            Ok(None) => return Err(user_not_found(id)),
+            Err(StoreError::VersionMismatch) if matches!(precondition, Precondition::Any) => {
+                continue
+            }
+            Err(err) => return Err(err.into()),
+        }
+    }
+
+    Err(ApiError::Conflict {
+        message: format!("User {} was modified concurrently, please retry", id),
+        fields: Vec::new(),
+    })
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/users/{id}",
+    tag = "users",
+    params(
+        ("id" = i32, Path),
+        ("If-Match" = Option<String>, Header, description = "之前响应中的 ETag，或 *"),
+    ),
+    responses(
+        (status = 204, description = "已软删除"),
+        (status = 404, body = ApiError),
+        (status = 412, description = "If-Match 与当前版本不一致", body = ApiError),
+        (status = 428, description = "严格模式下未携带 If-Match", body = ApiError),
+    )
+)]
+pub(super) async fn delete_user<S: UserStore>(
+    State(state): State<AppState<S>>,
+    Path(id): Path<i32>,
+    if_match: IfMatch,
+    ctx: AuditContext,
+) -> Result<StatusCode, ApiError> {
+    let precondition = if_match.into_precondition(state.require_if_match)?;
+    let event = state
+        .store
+        .delete_user(id, &precondition, &ctx)
+        .await?
+        .ok_or_else(|| user_not_found(id))?;
+    state.events.publish(event);
+    Ok(StatusCode::NO_CONTENT)
+}
+
+// 用户的变更历史，按时间倒序分页。记录中包含姓名和邮箱，仅限 admin 角色
+#[utoipa::path(
+    get,
+    path = "/api/admin/users/{id}/history",
+    tag = "admin",
+    security(("bearer" = [])),
+    params(("id" = i32, Path), HistoryParams),
+    responses(
+        (status = 200, description = "按时间倒序的一页审计记录", body = Page<AuditEntry>),
+        (status = 401, body = ApiError),
+        (status = 403, description = "缺少 admin 角色", body = ApiError),
+        (status = 422, body = ApiError),
+    )
+)]
+pub(super) async fn get_user_history<S: UserStore>(
+    State(state): State<AppState<S>>,
+    principal: Principal,
+    Path(id): Path<i32>,
+    Query(params): Query<HistoryParams>,
+) -> Result<Json<Page<AuditEntry>>, ApiError> {
+    principal.require_role(ADMIN_ROLE)?;
+    let query = params.into_query()?;
+
+    // 多取一条用于判断是否还有下一页
+    let fetch = HistoryQuery {
+        limit: query.limit + 1,
+        ..query.clone()
+    };
+    let rows = state.store.user_history(id, &fetch).await?;
+    Ok(Json(Page::from_entries(rows, &query)))
+}
+
+// 恢复软删除的用户
+#[utoipa::path(
+    post,
+    path = "/api/users/{id}/restore",
+    tag = "users",
+    params(("id" = i32, Path)),
+    responses(
+        (status = 200, body = User, headers(("ETag" = String))),
+        (status = 404, description = "没有该 id 的软删除用户", body = ApiError),
+        (status = 409, description = "邮箱已被其他用户使用", body = ApiError),
+    )
+)]
+pub(super) async fn restore_user<S: UserStore>(
+    State(state): State<AppState<S>>,
+    Path(id): Path<i32>,
+    ctx: AuditContext,
+) -> Result<TaggedUser, ApiError> {
+    let (user, event) = state
+        .store
+        .restore_user(id, &ctx)
+        .await?
+        .ok_or_else(|| ApiError::NotFound(format!("Deleted user {} not found", id)))?;
+    state.events.publish(event);
+
+    Ok(tagged(user))
+}
+
+#[derive(Serialize, ToSchema)]
+pub(super) struct PurgeReport {
+    purged: u64,
+    deleted_before: DateTime<Utc>,
+}
+
+// 永久删除超过保留期的软删除用户，仅限 admin 角色
+#[utoipa::path(
+    post,
+    path = "/api/admin/users:purge",
+    tag = "admin",
+    security(("bearer" = [])),
+    responses(
+        (status = 200, body = PurgeReport),
+        (status = 401, body = ApiError),
+        (status = 403, description = "缺少 admin 角色", body = ApiError),
+    )
+)]
+pub(super) async fn purge_users<S: UserStore>(
+    State(state): State<AppState<S>>,
+    principal: Principal,
+    ctx: AuditContext,
+) -> Result<Json<PurgeReport>, ApiError> {
+    principal.require_role(ADMIN_ROLE)?;
+
+    // 被清除的用户在软删除时已推送过事件，这里不再推送
+    let deleted_before = Utc::now() - state.purge_retention;
+    let purged = state.store.purge_deleted(deleted_before, &ctx).await?;
+    tracing::info!(purged, %deleted_before, subject = %principal.subject, "purged deleted users");
+
+    Ok(Json(PurgeReport {
+        purged,
+        deleted_before,
+    }))
+}
+
+// 用户路由，两个服务端二进制共用
+pub fn user_router<S: UserStore>(state: AppState<S>) -> Router {
+    Router::new()
+        .route("/api/users", get(get_users::<S>).post(create_user::<S>))
+        .route(
+            "/api/users:bulk",
+            post(import_users::<S>).layer(DefaultBodyLimit::max(IMPORT_BODY_LIMIT)),
+        )
+        .route("/api/users:export", get(export_users::<S>))
+        .route("/api/users/events", get(sse_events::<S>))
+        .route("/api/users/events/ws", get(ws_events::<S>))
+        .route(
+            "/api/users/{id}",
+            get(get_user::<S>)
+                .put(replace_user::<S>)
+                .patch(patch_user::<S>)
+                .delete(delete_user::<S>),
+        )
+        .route("/api/users/{id}/restore", post(restore_user::<S>))
+        .with_state(state)
+}
+
+// 管理路由，调用方需挂载认证中间件，处理函数自行检查角色
+pub fn admin_router<S: UserStore>(state: AppState<S>) -> Router {
+    Router::new()
+        .route("/api/admin/users:purge", post(purge_users::<S>))
+        .route("/api/admin/users/{id}/history", get(get_user_history::<S>))
+        .with_state(state)
+}
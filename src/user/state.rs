@@ -0,0 +1,42 @@
+use chrono::Duration;
+
+use super::events::UserEvents;
+
+// 应用状态
+#[derive(Clone)]
+pub struct AppState<S> {
+    pub store: S,
+    // 严格模式：PUT/DELETE 必须携带 If-Match
+    pub require_if_match: bool,
+    // 软删除用户的保留期，清除时只删除早于该期限的用户
+    pub purge_retention: Duration,
+    // 推送给 SSE/WebSocket 客户端的变更事件
+    pub events: UserEvents,
+}
+
+impl<S> AppState<S> {
+    pub fn new(store: S) -> Self {
+        Self {
+            store,
+            require_if_match: false,
+            purge_retention: Duration::days(30),
+            events: UserEvents::default(),
+        }
+    }
+
+    pub fn require_if_match(mut self, required: bool) -> Self {
+        self.require_if_match = required;
+        self
+    }
+
+    pub fn purge_retention(mut self, retention: Duration) -> Self {
+        self.purge_retention = retention;
+        self
+    }
+
+    // 断线重连时可补发的最近事件数
+    pub fn event_replay_capacity(mut self, capacity: usize) -> Self {
+        self.events = UserEvents::new(capacity);
+        self
+    }
+}
@@ -0,0 +1,167 @@
+use std::{error::Error, fmt, future::Future};
+
+use chrono::{DateTime, Utc};
+
+use super::audit::{AuditContext, AuditEntry, HistoryQuery};
+use super::model::{CreateUserRequest, User};
+use super::outbox::UserEvent;
+use super::query::UserQuery;
+
+// 存储层错误
+#[derive(Debug)]
+pub enum StoreError {
+    // 唯一约束冲突，携带冲突的字段名
+    Conflict(String),
+    // 版本与 If-Match 不一致
+    VersionMismatch,
+    Database(sqlx::Error),
+}
+
+impl fmt::Display for StoreError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            StoreError::Conflict(field) => write!(f, "Duplicate value for {}", field),
+            StoreError::VersionMismatch => write!(f, "Version mismatch"),
+            StoreError::Database(err) => write!(f, "Database error: {}", err),
+        }
+    }
+}
+
+impl StoreError {
+    // 序列化失败（40001）和死锁（40P01）可以通过重新执行整个事务解决
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            StoreError::Database(sqlx::Error::Database(db_err)) => {
+                matches!(db_err.code().as_deref(), Some("40001" | "40P01"))
+            }
+            _ => false,
+        }
+    }
+
+    // 语句超过 statement_timeout 被取消（57014），或等待连接池超时
+    pub fn is_timeout(&self) -> bool {
+        match self {
+            StoreError::Database(sqlx::Error::Database(db_err)) => {
+                db_err.code().as_deref() == Some("57014")
+            }
+            StoreError::Database(sqlx::Error::PoolTimedOut) => true,
+            _ => false,
+        }
+    }
+}
+
+impl Error for StoreError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            StoreError::Conflict(_) | StoreError::VersionMismatch => None,
+            StoreError::Database(err) => Some(err),
+        }
+    }
+}
+
+impl From<sqlx::Error> for StoreError {
+    fn from(err: sqlx::Error) -> Self {
+        if let sqlx::Error::Database(db_err) = &err {
+            if db_err.is_unique_violation() {
+                let field = match db_err.constraint() {
+                    Some("users_email_key" | "users_email_active_key") => "email",
+                    Some(constraint) => constraint,
+                    None => "unknown",
+                };
+                return StoreError::Conflict(field.to_string());
+            }
+        }
+        StoreError::Database(err)
+    }
+}
+
+// 写操作的版本前置条件
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Precondition {
+    Any,
+    // 当前版本必须是其中之一，列表为空时永远不匹配
+    Versions(Vec<i32>),
+}
+
+impl Precondition {
+    pub fn allows(&self, version: i32) -> bool {
+        match self {
+            Precondition::Any => true,
+            Precondition::Versions(versions) => versions.contains(&version),
+        }
+    }
+
+    pub fn versions(&self) -> Option<&[i32]> {
+        match self {
+            Precondition::Any => None,
+            Precondition::Versions(versions) => Some(versions),
+        }
+    }
+}
+
+// 批量创建中单行的结果：新建的用户及其事件，或该行的冲突
+pub type CreateOutcome = Result<(User, UserEvent), StoreError>;
+
+// 用户存储抽象，内存实现和 Postgres 实现共用同一套路由
+pub trait UserStore: Clone + Send + Sync + 'static {
+    // 所有写操作在同一事务中记录审计日志和 outbox 事件，并返回写入的事件，
+    // 进程内推送与 webhook 投递使用同一个事件 id
+    fn create_user(
+        &self,
+        input: &CreateUserRequest,
+        ctx: &AuditContext,
+    ) -> impl Future<Output = Result<(User, UserEvent), StoreError>> + Send;
+
+    // 批量创建，按输入顺序逐条返回结果；邮箱冲突（包括同一批次内重复）只影响对应的行
+    fn create_users(
+        &self,
+        inputs: &[CreateUserRequest],
+        ctx: &AuditContext,
+    ) -> impl Future<Output = Result<Vec<CreateOutcome>, StoreError>> + Send;
+
+    fn get_user(&self, id: i32) -> impl Future<Output = Result<Option<User>, StoreError>> + Send;
+
+    // 按查询条件排序、过滤并返回最多 query.limit 条
+    fn list_users(
+        &self,
+        query: &UserQuery,
+    ) -> impl Future<Output = Result<Vec<User>, StoreError>> + Send;
+
+    // 整体替换可修改字段；用户不存在时返回 None，版本不满足前置条件时返回 VersionMismatch
+    fn replace_user(
+        &self,
+        id: i32,
+        input: &CreateUserRequest,
+        precondition: &Precondition,
+        ctx: &AuditContext,
+    ) -> impl Future<Output = Result<Option<(User, UserEvent)>, StoreError>> + Send;
+
+    // 软删除，之后常规查询不再返回该用户；用户不存在时返回 None
+    fn delete_user(
+        &self,
+        id: i32,
+        precondition: &Precondition,
+        ctx: &AuditContext,
+    ) -> impl Future<Output = Result<Option<UserEvent>, StoreError>> + Send;
+
+    // 恢复软删除的用户；用户不存在或未被删除时返回 None，邮箱已被占用时返回 Conflict
+    fn restore_user(
+        &self,
+        id: i32,
+        ctx: &AuditContext,
+    ) -> impl Future<Output = Result<Option<(User, UserEvent)>, StoreError>> + Send;
+
+    // 永久删除在 deleted_before 之前软删除的用户，返回删除的数量
+    fn purge_deleted(
+        &self,
+        deleted_before: DateTime<Utc>,
+        ctx: &AuditContext,
+    ) -> impl Future<Output = Result<u64, StoreError>> + Send;
+
+    // 用户的变更记录，按时间倒序，包括已删除和已清除的用户
+    fn user_history(
+        &self,
+        user_id: i32,
+        query: &HistoryQuery,
+    ) -> impl Future<Output = Result<Vec<AuditEntry>, StoreError>> + Send;
+}
@@ -0,0 +1,156 @@
+use std::convert::Infallible;
+
+use axum::{
+    extract::{
+        ws::{Message, WebSocket, WebSocketUpgrade},
+        State,
+    },
+    http::{HeaderMap, StatusCode},
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        Response,
+    },
+};
+use futures::{stream, Stream, StreamExt};
+use serde::Deserialize;
+use tokio::sync::broadcast::{error::RecvError, Receiver};
+use utoipa::IntoParams;
+
+use super::events::{StreamEvent, Subscription};
+use super::state::AppState;
+use crate::error::ApiError;
+use crate::extract::Query;
+
+// 重放缓冲区无法补齐时发送，客户端应重新拉取用户列表
+const RESET: &str = r#"{"type":"reset"}"#;
+
+enum Item {
+    Event(StreamEvent),
+    Reset,
+}
+
+// 先补发，再接收实时事件；接收端落后于广播时以 reset 代替丢失的事件
+fn items(subscription: Subscription) -> impl Stream<Item = Item> + Send {
+    let Subscription {
+        replay,
+        gap,
+        receiver,
+    } = subscription;
+    let head = gap
+        .then_some(Item::Reset)
+        .into_iter()
+        .chain(replay.into_iter().map(Item::Event));
+    let live = stream::unfold(receiver, |mut receiver: Receiver<StreamEvent>| async move {
+        match receiver.recv().await {
+            Ok(event) => Some((Item::Event(event), receiver)),
+            Err(RecvError::Lagged(_)) => Some((Item::Reset, receiver)),
+            Err(RecvError::Closed) => None,
+        }
+    });
+    stream::iter(head).chain(live)
+}
+
+fn parse_last_event_id(value: &str) -> Result<u64, ApiError> {
+    value.trim().parse().map_err(|_| ApiError::Rejected {
+        status: StatusCode::BAD_REQUEST,
+        message: "Last-Event-ID must be a non-negative integer".to_string(),
+    })
+}
+
+// GET /api/users/events，断线重连时浏览器会自动携带 Last-Event-ID
+#[utoipa::path(
+    get,
+    path = "/api/users/events",
+    tag = "users",
+    params(("Last-Event-ID" = Option<u64>, Header, description = "最后收到的事件 id")),
+    responses(
+        (
+            status = 200,
+            description = "用户变更事件流，event 为事件类型，data 为事件 JSON；\
+                           无法补发时发送 reset 事件",
+            content_type = "text/event-stream",
+            body = String,
+        ),
+        (status = 400, description = "Last-Event-ID 不合法", body = ApiError),
+    )
+)]
+pub(super) async fn sse_events<S>(
+    State(state): State<AppState<S>>,
+    headers: HeaderMap,
+) -> Result<Sse<impl Stream<Item = Result<Event, Infallible>>>, ApiError> {
+    let last_seen = headers
+        .get("last-event-id")
+        .map(|value| parse_last_event_id(value.to_str().unwrap_or_default()))
+        .transpose()?;
+
+    let events = items(state.events.subscribe(last_seen)).map(|item| {
+        Ok(match item {
+            Item::Event(event) => Event::default()
+                .id(event.seq.to_string())
+                .event(event.event_type.as_str())
+                .data(event.payload.to_string()),
+            Item::Reset => Event::default().event("reset").data(RESET),
+        })
+    });
+    Ok(Sse::new(events).keep_alive(KeepAlive::default()))
+}
+
+// WebSocket 无法设置请求头，通过查询参数传入最后收到的 seq
+#[derive(Debug, Default, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub(super) struct StreamParams {
+    /// 最后收到的事件 seq
+    #[param(value_type = Option<u64>)]
+    last_event_id: Option<String>,
+}
+
+// GET /api/users/events/ws，每个事件一条文本消息 {"seq", "type", "payload"}
+#[utoipa::path(
+    get,
+    path = "/api/users/events/ws",
+    tag = "users",
+    params(StreamParams),
+    responses(
+        (status = 101, description = "升级为 WebSocket，每个事件一条 StreamEvent 文本消息"),
+        (status = 400, description = "last_event_id 不合法", body = ApiError),
+    )
+)]
+pub(super) async fn ws_events<S>(
+    State(state): State<AppState<S>>,
+    Query(params): Query<StreamParams>,
+    upgrade: WebSocketUpgrade,
+) -> Result<Response, ApiError> {
+    let last_seen = params
+        .last_event_id
+        .as_deref()
+        .map(parse_last_event_id)
+        .transpose()?;
+    let subscription = state.events.subscribe(last_seen);
+    Ok(upgrade.on_upgrade(move |socket| forward(socket, subscription)))
+}
+
+async fn forward(mut socket: WebSocket, subscription: Subscription) {
+    let mut items = Box::pin(items(subscription));
+    loop {
+        tokio::select! {
+            item = items.next() => {
+                let text = match item {
+                    Some(Item::Event(event)) => {
+                        serde_json::to_string(&event).expect("event is always serializable")
+                    }
+                    Some(Item::Reset) => RESET.to_string(),
+                    None => break,
+                };
+                if socket.send(Message::text(text)).await.is_err() {
+                    return;
+                }
+            }
+            // 客户端发来的消息只用于检测断开，ping 由底层自动回复
+            message = socket.recv() => match message {
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => return,
+                Some(Ok(_)) => {}
+            },
+        }
+    }
+    let _ = socket.send(Message::Close(None)).await;
+}
@@ -0,0 +1,457 @@
+use std::collections::{HashMap, HashSet};
+use std::ops::DerefMut;
+
+use chrono::{DateTime, Utc};
+use serde_json::Value;
+use sqlx::{pool::PoolConnection, PgConnection, Postgres, QueryBuilder, Transaction};
+
+use super::audit::{snapshot, AuditAction, AuditContext, AuditEntry, AuditRecord, HistoryQuery};
+use super::model::{CreateUserRequest, User};
+use super::outbox::{write_events, UserEvent, UserEventType};
+use super::query::{SortField, SortKey, UserQuery};
+use super::store::{CreateOutcome, Precondition, StoreError};
+
+// 绑定到一个连接上的用户仓储操作；读操作可以在普通连接或事务上执行，
+// 写操作只在事务上提供，以保证变更、审计记录和 outbox 事件一同提交
+pub struct UserSession<C> {
+    conn: C,
+}
+
+// 从连接池借出的普通连接
+pub type PooledSession = UserSession<PoolConnection<Postgres>>;
+
+// 事务范围的仓储句柄，drop 时未提交的变更会回滚
+pub type UnitOfWork = UserSession<Transaction<'static, Postgres>>;
+
+impl<C> UserSession<C>
+where
+    C: DerefMut<Target = PgConnection> + Send,
+{
+    pub fn new(conn: C) -> Self {
+        Self { conn }
+    }
+
+    // 底层连接，用于在同一事务中执行其他表的操作
+    pub fn connection(&mut self) -> &mut PgConnection {
+        &mut self.conn
+    }
+
+    pub async fn get_user(&mut self, id: i32) -> Result<Option<User>, StoreError> {
+        let user = sqlx::query_as::<_, User>(
+            r#"
+            SELECT id, name, email, created_at, updated_at, version
+            FROM users
+            WHERE id = $1 AND deleted_at IS NULL
+            "#,
+        )
+        .bind(id)
+        .fetch_optional(&mut *self.conn)
+        .await?;
+
+        Ok(user)
+    }
+
+    pub async fn list_users(&mut self, query: &UserQuery) -> Result<Vec<User>, StoreError> {
+        // 名称按字节序比较（COLLATE "C"），与内存实现的排序结果保持一致
+        let mut builder = QueryBuilder::<Postgres>::new(
+            "SELECT id, name, email, created_at, updated_at, version FROM users WHERE deleted_at IS NULL",
+        );
+
+        if let Some(prefix) = &query.filter.name_prefix {
+            builder
+                .push(" AND starts_with(name, ")
+                .push_bind(prefix)
+                .push(")");
+        }
+        if let Some(domain) = &query.filter.email_domain {
+            builder
+                .push(" AND lower(regexp_replace(email, '^.*@', '')) = lower(")
+                .push_bind(domain)
+                .push(")");
+        }
+
+        let (op, direction) = if query.sort.descending {
+            ("<", "DESC")
+        } else {
+            (">", "ASC")
+        };
+        if let Some(cursor) = &query.after {
+            match &cursor.key {
+                SortKey::Id => {
+                    builder
+                        .push(format!(" AND id {} ", op))
+                        .push_bind(cursor.id);
+                }
+                SortKey::Name(name) => {
+                    builder
+                        .push(format!(" AND (name COLLATE \"C\", id) {} (", op))
+                        .push_bind(name)
+                        .push(", ")
+                        .push_bind(cursor.id)
+                        .push(")");
+                }
+                SortKey::CreatedAt(created_at) => {
+                    builder
+                        .push(format!(" AND (created_at, id) {} (", op))
+                        .push_bind(*created_at)
+                        .push(", ")
+                        .push_bind(cursor.id)
+                        .push(")");
+                }
+            }
+        }
+
+        let sort_column = match query.sort.field {
+            SortField::Id => None,
+            SortField::Name => Some("name COLLATE \"C\""),
+            SortField::CreatedAt => Some("created_at"),
+        };
+        builder.push(" ORDER BY ");
+        if let Some(column) = sort_column {
+            builder.push(format!("{} {}, ", column, direction));
+        }
+        builder
+            .push(format!("id {} LIMIT ", direction))
+            .push_bind(i64::from(query.limit));
+
+        let users = builder
+            .build_query_as::<User>()
+            .fetch_all(&mut *self.conn)
+            .await?;
+        Ok(users)
+    }
+
+    pub async fn user_history(
+        &mut self,
+        user_id: i32,
+        query: &HistoryQuery,
+    ) -> Result<Vec<AuditEntry>, StoreError> {
+        let entries = sqlx::query_as::<_, AuditEntry>(
+            r#"
+            SELECT id, user_id, action, actor, request_id, before, after, created_at
+            FROM user_audit_log
+            WHERE user_id = $1 AND ($2::BIGINT IS NULL OR id < $2)
+            ORDER BY id DESC
+            LIMIT $3
+            "#,
+        )
+        .bind(user_id)
+        .bind(query.before_id)
+        .bind(i64::from(query.limit))
+        .fetch_all(&mut *self.conn)
+        .await?;
+
+        Ok(entries)
+    }
+}
+
+impl UnitOfWork {
+    pub async fn commit(self) -> Result<(), StoreError> {
+        self.conn.commit().await?;
+        Ok(())
+    }
+
+    pub async fn create_user(
+        &mut self,
+        input: &CreateUserRequest,
+        ctx: &AuditContext,
+    ) -> Result<(User, UserEvent), StoreError> {
+        let user = sqlx::query_as::<_, User>(
+            r#"
+            INSERT INTO users (name, email)
+            VALUES ($1, $2)
+            RETURNING id, name, email, created_at, updated_at, version
+            "#,
+        )
+        .bind(&input.name)
+        .bind(&input.email)
+        .fetch_one(&mut *self.conn)
+        .await?;
+
+        let after = snapshot(&user, None);
+        let record = AuditRecord::new(user.id, AuditAction::Create, None, Some(after));
+        write_audit(&mut self.conn, ctx, &[record]).await?;
+        let event = UserEvent::with_user(UserEventType::Created, &user);
+        write_events(&mut self.conn, std::slice::from_ref(&event)).await?;
+        Ok((user, event))
+    }
+
+    // 单条多行 INSERT，冲突的行由 ON CONFLICT 跳过，再按邮箱对应回输入
+    pub async fn create_users(
+        &mut self,
+        inputs: &[CreateUserRequest],
+        ctx: &AuditContext,
+    ) -> Result<Vec<CreateOutcome>, StoreError> {
+        let mut seen = HashSet::new();
+        let unique: Vec<&CreateUserRequest> = inputs
+            .iter()
+            .filter(|input| seen.insert(input.email.as_str()))
+            .collect();
+        let names: Vec<&str> = unique.iter().map(|input| input.name.as_str()).collect();
+        let emails: Vec<&str> = unique.iter().map(|input| input.email.as_str()).collect();
+
+        let users = sqlx::query_as::<_, User>(
+            r#"
+            INSERT INTO users (name, email)
+            SELECT * FROM UNNEST($1::TEXT[], $2::TEXT[])
+            ON CONFLICT (email) WHERE deleted_at IS NULL DO NOTHING
+            RETURNING id, name, email, created_at, updated_at, version
+            "#,
+        )
+        .bind(&names)
+        .bind(&emails)
+        .fetch_all(&mut *self.conn)
+        .await?;
+
+        let records: Vec<AuditRecord> = users
+            .iter()
+            .map(|user| {
+                AuditRecord::new(
+                    user.id,
+                    AuditAction::Create,
+                    None,
+                    Some(snapshot(user, None)),
+                )
+            })
+            .collect();
+        write_audit(&mut self.conn, ctx, &records).await?;
+        let events: Vec<UserEvent> = users
+            .iter()
+            .map(|user| UserEvent::with_user(UserEventType::Created, user))
+            .collect();
+        write_events(&mut self.conn, &events).await?;
+
+        let mut created: HashMap<String, (User, UserEvent)> = users
+            .into_iter()
+            .zip(events)
+            .map(|(user, event)| (user.email.clone(), (user, event)))
+            .collect();
+        Ok(inputs
+            .iter()
+            .map(|input| {
+                created
+                    .remove(&input.email)
+                    .ok_or_else(|| StoreError::Conflict("email".to_string()))
+            })
+            .collect())
+    }
+
+    pub async fn replace_user(
+        &mut self,
+        id: i32,
+        input: &CreateUserRequest,
+        precondition: &Precondition,
+        ctx: &AuditContext,
+    ) -> Result<Option<(User, UserEvent)>, StoreError> {
+        let Some(current) = lock_active(&mut self.conn, id).await? else {
+            return Ok(None);
+        };
+        if !precondition.allows(current.version) {
+            return Err(StoreError::VersionMismatch);
+        }
+
+        let user = sqlx::query_as::<_, User>(
+            r#"
+            UPDATE users
+            SET name = $1,
+                email = $2,
+                updated_at = CURRENT_TIMESTAMP,
+                version = version + 1
+            WHERE id = $3
+            RETURNING id, name, email, created_at, updated_at, version
+            "#,
+        )
+        .bind(&input.name)
+        .bind(&input.email)
+        .bind(id)
+        .fetch_one(&mut *self.conn)
+        .await?;
+
+        let record = AuditRecord::new(
+            id,
+            AuditAction::Replace,
+            Some(snapshot(&current, None)),
+            Some(snapshot(&user, None)),
+        );
+        write_audit(&mut self.conn, ctx, &[record]).await?;
+        let event = UserEvent::with_user(UserEventType::Updated, &user);
+        write_events(&mut self.conn, std::slice::from_ref(&event)).await?;
+        Ok(Some((user, event)))
+    }
+
+    pub async fn delete_user(
+        &mut self,
+        id: i32,
+        precondition: &Precondition,
+        ctx: &AuditContext,
+    ) -> Result<Option<UserEvent>, StoreError> {
+        let Some(current) = lock_active(&mut self.conn, id).await? else {
+            return Ok(None);
+        };
+        if !precondition.allows(current.version) {
+            return Err(StoreError::VersionMismatch);
+        }
+
+        let deleted_at = sqlx::query_scalar::<_, DateTime<Utc>>(
+            r#"
+            UPDATE users
+            SET deleted_at = CURRENT_TIMESTAMP,
+                version = version + 1
+            WHERE id = $1
+            RETURNING deleted_at
+            "#,
+        )
+        .bind(id)
+        .fetch_one(&mut *self.conn)
+        .await?;
+
+        let record = AuditRecord::new(
+            id,
+            AuditAction::Delete,
+            Some(snapshot(&current, None)),
+            Some(snapshot(&current, Some(deleted_at))),
+        );
+        write_audit(&mut self.conn, ctx, &[record]).await?;
+        let event = UserEvent::with_id(UserEventType::Deleted, id);
+        write_events(&mut self.conn, std::slice::from_ref(&event)).await?;
+        Ok(Some(event))
+    }
+
+    pub async fn restore_user(
+        &mut self,
+        id: i32,
+        ctx: &AuditContext,
+    ) -> Result<Option<(User, UserEvent)>, StoreError> {
+        let deleted_at = sqlx::query_scalar::<_, DateTime<Utc>>(
+            "SELECT deleted_at FROM users WHERE id = $1 AND deleted_at IS NOT NULL FOR UPDATE",
+        )
+        .bind(id)
+        .fetch_optional(&mut *self.conn)
+        .await?;
+        let Some(deleted_at) = deleted_at else {
+            return Ok(None);
+        };
+
+        let user = sqlx::query_as::<_, User>(
+            r#"
+            UPDATE users
+            SET deleted_at = NULL,
+                updated_at = CURRENT_TIMESTAMP,
+                version = version + 1
+            WHERE id = $1
+            RETURNING id, name, email, created_at, updated_at, version
+            "#,
+        )
+        .bind(id)
+        .fetch_one(&mut *self.conn)
+        .await?;
+
+        let record = AuditRecord::new(
+            id,
+            AuditAction::Restore,
+            Some(snapshot(&user, Some(deleted_at))),
+            Some(snapshot(&user, None)),
+        );
+        write_audit(&mut self.conn, ctx, &[record]).await?;
+        let event = UserEvent::with_user(UserEventType::Restored, &user);
+        write_events(&mut self.conn, std::slice::from_ref(&event)).await?;
+        Ok(Some((user, event)))
+    }
+
+    pub async fn purge_deleted(
+        &mut self,
+        deleted_before: DateTime<Utc>,
+        ctx: &AuditContext,
+    ) -> Result<u64, StoreError> {
+        let purged: Vec<(User, DateTime<Utc>)> = sqlx::query_as::<_, PurgedUser>(
+            r#"
+            DELETE FROM users
+            WHERE deleted_at < $1
+            RETURNING id, name, email, created_at, updated_at, version, deleted_at
+            "#,
+        )
+        .bind(deleted_before)
+        .fetch_all(&mut *self.conn)
+        .await?
+        .into_iter()
+        .map(|row| (row.user, row.deleted_at))
+        .collect();
+
+        let records: Vec<AuditRecord> = purged
+            .iter()
+            .map(|(user, deleted_at)| {
+                let before = snapshot(user, Some(*deleted_at));
+                AuditRecord::new(user.id, AuditAction::Purge, Some(before), None)
+            })
+            .collect();
+        write_audit(&mut self.conn, ctx, &records).await?;
+        let events: Vec<UserEvent> = purged
+            .iter()
+            .map(|(user, _)| UserEvent::with_id(UserEventType::Purged, user.id))
+            .collect();
+        write_events(&mut self.conn, &events).await?;
+        Ok(purged.len() as u64)
+    }
+}
+
+// 清除时返回的行
+#[derive(sqlx::FromRow)]
+struct PurgedUser {
+    #[sqlx(flatten)]
+    user: User,
+    deleted_at: DateTime<Utc>,
+}
+
+// 锁定未删除的用户行，供事务内读取变更前的状态
+async fn lock_active(conn: &mut PgConnection, id: i32) -> Result<Option<User>, sqlx::Error> {
+    sqlx::query_as::<_, User>(
+        r#"
+        SELECT id, name, email, created_at, updated_at, version
+        FROM users
+        WHERE id = $1 AND deleted_at IS NULL
+        FOR UPDATE
+        "#,
+    )
+    .bind(id)
+    .fetch_optional(conn)
+    .await
+}
+
+// 在调用方的事务中追加审计记录
+async fn write_audit(
+    conn: &mut PgConnection,
+    ctx: &AuditContext,
+    records: &[AuditRecord],
+) -> Result<(), sqlx::Error> {
+    if records.is_empty() {
+        return Ok(());
+    }
+    let user_ids: Vec<i32> = records.iter().map(|record| record.user_id).collect();
+    let actions: Vec<&str> = records
+        .iter()
+        .map(|record| record.action.as_str())
+        .collect();
+    let befores: Vec<Option<&Value>> = records
+        .iter()
+        .map(|record| record.before.as_ref())
+        .collect();
+    let afters: Vec<Option<&Value>> = records.iter().map(|record| record.after.as_ref()).collect();
+
+    sqlx::query(
+        r#"
+        INSERT INTO user_audit_log (user_id, action, actor, request_id, before, after)
+        SELECT user_id, action, $3, $4, before, after
+        FROM UNNEST($1::INTEGER[], $2::TEXT[], $5::JSONB[], $6::JSONB[])
+            AS t(user_id, action, before, after)
+        "#,
+    )
+    .bind(&user_ids)
+    .bind(&actions)
+    .bind(&ctx.actor)
+    .bind(&ctx.request_id)
+    .bind(&befores)
+    .bind(&afters)
+    .execute(conn)
+    .await?;
+    Ok(())
+}
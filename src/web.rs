@@ -0,0 +1,130 @@
+use std::time::Duration;
+
+use axum::{
+    http::{header, HeaderName, HeaderValue, Method},
+    Router,
+};
+use serde::{Deserialize, Serialize};
+use tower_http::{
+    compression::{
+        predicate::{NotForContentType, Predicate, SizeAbove},
+        CompressionLayer,
+    },
+    cors::{AllowOrigin, CorsLayer},
+    set_header::SetResponseHeaderLayer,
+};
+
+use crate::config::{CorsConfig, HttpConfig};
+
+// 浏览器跨域请求可以读取的响应头
+const EXPOSED_HEADERS: [HeaderName; 6] = [
+    header::ETAG,
+    header::RETRY_AFTER,
+    HeaderName::from_static("ratelimit-limit"),
+    HeaderName::from_static("ratelimit-remaining"),
+    HeaderName::from_static("ratelimit-reset"),
+    HeaderName::from_static("x-request-id"),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FrameOptions {
+    Deny,
+    SameOrigin,
+}
+
+impl FrameOptions {
+    fn header_value(self) -> HeaderValue {
+        HeaderValue::from_static(match self {
+            FrameOptions::Deny => "DENY",
+            FrameOptions::SameOrigin => "SAMEORIGIN",
+        })
+    }
+}
+
+// CORS、安全响应头和响应压缩，两个服务端二进制共用。
+// 以 layer 挂载在整个应用外层，限流、认证等中间件产生的错误响应同样带上这些头
+#[derive(Clone)]
+pub struct WebLayers {
+    cors: Option<CorsLayer>,
+    hsts: Option<HeaderValue>,
+    frame_options: HeaderValue,
+    compression: Option<u16>,
+}
+
+impl WebLayers {
+    // 配置已通过 Config::validate 校验，这里的解析不会失败
+    pub fn new(config: &HttpConfig) -> Self {
+        let hsts = (config.hsts_max_age_secs > 0).then(|| {
+            let mut value = format!("max-age={}", config.hsts_max_age_secs);
+            if config.hsts_include_subdomains {
+                value.push_str("; includeSubDomains");
+            }
+            HeaderValue::try_from(value).expect("hsts value is always a valid header")
+        });
+        WebLayers {
+            cors: (!config.cors.allowed_origins.is_empty()).then(|| cors_layer(&config.cors)),
+            hsts,
+            frame_options: config.frame_options.header_value(),
+            compression: config.compression.then_some(config.compression_min_bytes),
+        }
+    }
+
+    pub fn apply(&self, router: Router) -> Router {
+        let mut router = router;
+        // 按 Accept-Encoding 协商 zstd、br 或 gzip；SSE 需要逐条推送，图片本身已压缩
+        if let Some(min_bytes) = self.compression {
+            let predicate = SizeAbove::new(min_bytes)
+                .and(NotForContentType::GRPC)
+                .and(NotForContentType::IMAGES)
+                .and(NotForContentType::SSE);
+            router = router.layer(CompressionLayer::new().compress_when(predicate));
+        }
+
+        router = router
+            .layer(SetResponseHeaderLayer::if_not_present(
+                header::X_CONTENT_TYPE_OPTIONS,
+                HeaderValue::from_static("nosniff"),
+            ))
+            .layer(SetResponseHeaderLayer::if_not_present(
+                header::X_FRAME_OPTIONS,
+                self.frame_options.clone(),
+            ));
+        if let Some(hsts) = &self.hsts {
+            router = router.layer(SetResponseHeaderLayer::if_not_present(
+                header::STRICT_TRANSPORT_SECURITY,
+                hsts.clone(),
+            ));
+        }
+
+        // 预检请求由 CORS 层直接应答，不经过路由和认证
+        match &self.cors {
+            Some(cors) => router.layer(cors.clone()),
+            None => router,
+        }
+    }
+}
+
+fn cors_layer(config: &CorsConfig) -> CorsLayer {
+    let origin = if config.allowed_origins.iter().any(|origin| origin == "*") {
+        AllowOrigin::any()
+    } else {
+        AllowOrigin::list(config.allowed_origins.iter().map(|origin| {
+            HeaderValue::from_str(origin).expect("origin is validated by Config::validate")
+        }))
+    };
+    let methods = config.allowed_methods.iter().map(|method| {
+        Method::from_bytes(method.as_bytes()).expect("method is validated by Config::validate")
+    });
+    let headers = config.allowed_headers.iter().map(|name| {
+        HeaderName::from_bytes(name.as_bytes()).expect("header is validated by Config::validate")
+    });
+
+    CorsLayer::new()
+        .allow_origin(origin)
+        .allow_methods(methods.collect::<Vec<_>>())
+        .allow_headers(headers.collect::<Vec<_>>())
+        .expose_headers(EXPOSED_HEADERS)
+        .allow_credentials(config.allow_credentials)
+        .max_age(Duration::from_secs(config.max_age_secs))
+}
@@ -0,0 +1,363 @@
+use std::{collections::HashMap, error::Error, fmt, time::Duration};
+
+use axum::http::HeaderMap;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use chrono::Utc;
+use futures::future::join_all;
+use hmac::{Hmac, Mac};
+use serde_json::Value;
+use sha2::Sha256;
+use sqlx::PgPool;
+use tokio::task::JoinHandle;
+use uuid::Uuid;
+
+use crate::config::{WebhookConfig, WebhookEndpoint};
+use crate::shutdown::Shutdown;
+use crate::user::RetryPolicy;
+
+// Standard Webhooks 规范的请求头
+pub const WEBHOOK_ID: &str = "webhook-id";
+pub const WEBHOOK_TIMESTAMP: &str = "webhook-timestamp";
+pub const WEBHOOK_SIGNATURE: &str = "webhook-signature";
+
+// 接收方允许的时间戳偏差
+pub const DEFAULT_TOLERANCE: Duration = Duration::from_secs(5 * 60);
+
+type HmacSha256 = Hmac<Sha256>;
+
+// 签名内容为 "{id}.{timestamp}.{body}"，返回 webhook-signature 头的值
+pub fn sign(secret: &[u8], id: &str, timestamp: i64, body: &[u8]) -> String {
+    let signature = signer(secret, id, timestamp, body).finalize().into_bytes();
+    format!("v1,{}", STANDARD.encode(signature))
+}
+
+fn signer(secret: &[u8], id: &str, timestamp: i64, body: &[u8]) -> HmacSha256 {
+    let mut mac = HmacSha256::new_from_slice(secret).expect("HMAC accepts keys of any length");
+    mac.update(id.as_bytes());
+    mac.update(b".");
+    mac.update(timestamp.to_string().as_bytes());
+    mac.update(b".");
+    mac.update(body);
+    mac
+}
+
+#[derive(Debug)]
+pub enum VerifyError {
+    MissingHeader(&'static str),
+    InvalidTimestamp,
+    TimestampOutOfTolerance,
+    SignatureMismatch,
+}
+
+impl fmt::Display for VerifyError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            VerifyError::MissingHeader(name) => write!(f, "Missing {} header", name),
+            VerifyError::InvalidTimestamp => write!(f, "Invalid {} header", WEBHOOK_TIMESTAMP),
+            VerifyError::TimestampOutOfTolerance => write!(f, "Timestamp is outside the tolerance"),
+            VerifyError::SignatureMismatch => write!(f, "No matching signature"),
+        }
+    }
+}
+
+impl Error for VerifyError {}
+
+// 接收方校验签名；webhook-signature 可包含多个以空格分隔的签名，任一匹配即通过
+pub fn verify(
+    secret: &[u8],
+    headers: &HeaderMap,
+    body: &[u8],
+    tolerance: Duration,
+) -> Result<(), VerifyError> {
+    let header = |name: &'static str| {
+        headers
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+            .ok_or(VerifyError::MissingHeader(name))
+    };
+    let id = header(WEBHOOK_ID)?;
+    let timestamp: i64 = header(WEBHOOK_TIMESTAMP)?
+        .parse()
+        .map_err(|_| VerifyError::InvalidTimestamp)?;
+    let signatures = header(WEBHOOK_SIGNATURE)?;
+
+    if Utc::now().timestamp().abs_diff(timestamp) > tolerance.as_secs() {
+        return Err(VerifyError::TimestampOutOfTolerance);
+    }
+
+    let mac = signer(secret, id, timestamp, body);
+    let matched = signatures
+        .split(' ')
+        .filter_map(|signature| signature.strip_prefix("v1,"))
+        .filter_map(|encoded| STANDARD.decode(encoded).ok())
+        .any(|expected| mac.clone().verify_slice(&expected).is_ok());
+    if matched {
+        Ok(())
+    } else {
+        Err(VerifyError::SignatureMismatch)
+    }
+}
+
+// 从 outbox 读取用户事件，为订阅的端点生成投递任务并发送签名的 HTTP 请求；
+// 失败的投递按指数退避重试，达到最大次数后标记为 dead。
+// 多个实例可以同时运行，行锁保证同一投递不会被并发处理；
+// 重试可能导致同一端点收到的事件乱序，接收方应按 webhook-id 去重
+pub struct WebhookDispatcher {
+    pool: PgPool,
+    client: reqwest::Client,
+    endpoints: HashMap<String, WebhookEndpoint>,
+    poll_interval: Duration,
+    request_timeout: Duration,
+    retry: RetryPolicy,
+    batch_size: i64,
+}
+
+// 领取后待发送的投递
+#[derive(sqlx::FromRow)]
+struct Delivery {
+    id: i64,
+    endpoint: String,
+    attempts: i32,
+    event_id: Uuid,
+    payload: Value,
+}
+
+enum Outcome {
+    Delivered,
+    Failed(String),
+}
+
+impl WebhookDispatcher {
+    pub fn new(pool: PgPool, config: &WebhookConfig) -> Self {
+        let client = reqwest::Client::builder()
+            .timeout(config.request_timeout())
+            .build()
+            .expect("failed to build the webhook HTTP client");
+        let endpoints = config
+            .endpoints
+            .iter()
+            .map(|endpoint| (endpoint.name.clone(), endpoint.clone()))
+            .collect();
+        WebhookDispatcher {
+            pool,
+            client,
+            endpoints,
+            poll_interval: config.poll_interval(),
+            request_timeout: config.request_timeout(),
+            retry: RetryPolicy {
+                max_attempts: config.max_attempts,
+                base_delay: Duration::from_secs(config.backoff_base_secs),
+                max_delay: Duration::from_secs(config.backoff_max_secs),
+            },
+            batch_size: config.batch_size.into(),
+        }
+    }
+
+    // 在后台轮询，关闭信号触发后完成当前一轮再退出
+    pub fn spawn(self, shutdown: Shutdown) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            loop {
+                let busy = match self.run_once().await {
+                    Ok(processed) => processed > 0,
+                    Err(err) => {
+                        tracing::error!(error = %err, "webhook dispatch failed");
+                        false
+                    }
+                };
+                if busy && !shutdown.is_draining() {
+                    continue;
+                }
+                tokio::select! {
+                    _ = shutdown.wait() => break,
+                    _ = tokio::time::sleep(self.poll_interval) => {}
+                }
+            }
+            tracing::info!("webhook 分发器已停止");
+        })
+    }
+
+    // 执行一轮分发，返回处理的事件数和投递数之和
+    pub async fn run_once(&self) -> Result<usize, sqlx::Error> {
+        let fanned_out = self.fan_out().await?;
+        let deliveries = self.claim().await?;
+        let attempted = deliveries.len();
+
+        let outcomes = join_all(deliveries.iter().map(|delivery| self.send(delivery))).await;
+        for (delivery, outcome) in deliveries.iter().zip(outcomes) {
+            self.record(delivery, outcome).await?;
+        }
+        Ok(fanned_out + attempted)
+    }
+
+    // 为未分发的事件按订阅关系生成投递任务；没有订阅方的事件直接标记为已分发
+    async fn fan_out(&self) -> Result<usize, sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+        let events: Vec<(i64, String)> = sqlx::query_as(
+            r#"
+            SELECT id, event_type FROM user_outbox
+            WHERE dispatched_at IS NULL
+            ORDER BY id
+            LIMIT $1
+            FOR UPDATE SKIP LOCKED
+            "#,
+        )
+        .bind(self.batch_size)
+        .fetch_all(&mut *tx)
+        .await?;
+        if events.is_empty() {
+            return Ok(0);
+        }
+
+        let mut outbox_ids = Vec::new();
+        let mut endpoint_names = Vec::new();
+        for (id, event_type) in &events {
+            for endpoint in self.endpoints.values() {
+                if endpoint.events.is_empty() || endpoint.events.contains(event_type) {
+                    outbox_ids.push(*id);
+                    endpoint_names.push(endpoint.name.as_str());
+                }
+            }
+        }
+        sqlx::query(
+            r#"
+            INSERT INTO webhook_deliveries (outbox_id, endpoint)
+            SELECT * FROM UNNEST($1::BIGINT[], $2::TEXT[])
+            ON CONFLICT (outbox_id, endpoint) DO NOTHING
+            "#,
+        )
+        .bind(&outbox_ids)
+        .bind(&endpoint_names)
+        .execute(&mut *tx)
+        .await?;
+
+        let ids: Vec<i64> = events.iter().map(|(id, _)| *id).collect();
+        sqlx::query("UPDATE user_outbox SET dispatched_at = now() WHERE id = ANY($1)")
+            .bind(&ids)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+        Ok(events.len())
+    }
+
+    // 领取本实例配置的端点上到期的投递，并把下次尝试时间推迟一个租约，
+    // 进程在发送过程中退出时，租约到期后由其他实例重新领取。
+    // 其他端点的投递保持 pending，留给配置了该端点的实例（如滚动发布期间）
+    async fn claim(&self) -> Result<Vec<Delivery>, sqlx::Error> {
+        let lease = self.request_timeout * 2;
+        let endpoints: Vec<&str> = self.endpoints.keys().map(String::as_str).collect();
+        sqlx::query_as::<_, Delivery>(
+            r#"
+            UPDATE webhook_deliveries AS d
+            SET next_attempt_at = now() + make_interval(secs => $2)
+            FROM user_outbox AS o
+            WHERE o.id = d.outbox_id
+              AND d.id IN (
+                  SELECT id FROM webhook_deliveries
+                  WHERE status = 'pending'
+                    AND next_attempt_at <= now()
+                    AND endpoint = ANY($3)
+                  ORDER BY next_attempt_at
+                  LIMIT $1
+                  FOR UPDATE SKIP LOCKED
+              )
+            RETURNING d.id, d.endpoint, d.attempts, o.event_id, o.payload
+            "#,
+        )
+        .bind(self.batch_size)
+        .bind(lease.as_secs_f64())
+        .bind(&endpoints)
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    async fn send(&self, delivery: &Delivery) -> Outcome {
+        // claim 只领取已配置端点的投递
+        let Some(endpoint) = self.endpoints.get(&delivery.endpoint) else {
+            return Outcome::Failed("endpoint is not configured".to_string());
+        };
+
+        let id = delivery.event_id.to_string();
+        let timestamp = Utc::now().timestamp();
+        let body = serde_json::to_vec(&delivery.payload).expect("payload is always serializable");
+        let signature = sign(endpoint.secret.as_bytes(), &id, timestamp, &body);
+
+        let result = self
+            .client
+            .post(&endpoint.url)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .header(WEBHOOK_ID, &id)
+            .header(WEBHOOK_TIMESTAMP, timestamp.to_string())
+            .header(WEBHOOK_SIGNATURE, signature)
+            .body(body)
+            .send()
+            .await;
+        match result {
+            Ok(response) if response.status().is_success() => Outcome::Delivered,
+            Ok(response) => {
+                Outcome::Failed(format!("endpoint responded with {}", response.status()))
+            }
+            Err(err) => Outcome::Failed(format!("request failed: {}", err)),
+        }
+    }
+
+    async fn record(&self, delivery: &Delivery, outcome: Outcome) -> Result<(), sqlx::Error> {
+        let attempts = delivery.attempts + 1;
+        let result = match outcome {
+            Outcome::Delivered => {
+                sqlx::query(
+                    r#"
+                    UPDATE webhook_deliveries
+                    SET status = 'delivered', attempts = $2, delivered_at = now(), last_error = NULL
+                    WHERE id = $1
+                    "#,
+                )
+                .bind(delivery.id)
+                .bind(attempts)
+                .execute(&self.pool)
+                .await?;
+                "delivered"
+            }
+            Outcome::Failed(error) => {
+                let dead = attempts as u32 >= self.retry.max_attempts;
+                let delay = self.retry.backoff(attempts as u32);
+                tracing::warn!(
+                    endpoint = %delivery.endpoint,
+                    event_id = %delivery.event_id,
+                    attempts,
+                    dead,
+                    error = %error,
+                    "webhook delivery failed"
+                );
+                sqlx::query(
+                    r#"
+                    UPDATE webhook_deliveries
+                    SET status = CASE WHEN $3 THEN 'dead' ELSE 'pending' END,
+                        attempts = $2,
+                        next_attempt_at = now() + make_interval(secs => $4),
+                        last_error = $5
+                    WHERE id = $1
+                    "#,
+                )
+                .bind(delivery.id)
+                .bind(attempts)
+                .bind(dead)
+                .bind(delay.as_secs_f64())
+                .bind(&error)
+                .execute(&self.pool)
+                .await?;
+                if dead {
+                    "dead"
+                } else {
+                    "retry"
+                }
+            }
+        };
+        metrics::counter!(
+            "webhook_deliveries_total",
+            "endpoint" => delivery.endpoint.clone(),
+            "outcome" => result
+        )
+        .increment(1);
+        Ok(())
+    }
+}
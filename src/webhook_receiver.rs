@@ -0,0 +1,83 @@
+use std::{
+    collections::HashMap,
+    net::SocketAddr,
+    sync::{Arc, Mutex},
+};
+
+use axum::{
+    body::Bytes,
+    extract::State,
+    http::{HeaderMap, StatusCode},
+    routing::post,
+    Router,
+};
+use clap::Parser;
+use hello_rust::webhook::{self, DEFAULT_TOLERANCE, WEBHOOK_ID};
+
+// 本地调试用的 webhook 接收端：校验签名并把事件逐行打印到标准输出
+#[derive(Parser)]
+struct Cli {
+    /// 监听地址
+    #[arg(long, default_value = "127.0.0.1:4000")]
+    bind: SocketAddr,
+
+    /// 与 webhooks.endpoints 中配置一致的签名密钥
+    #[arg(long, env = "WEBHOOK_SECRET")]
+    secret: String,
+
+    /// 每个事件的前 N 次投递返回 500，用于观察重试
+    #[arg(long, default_value_t = 0)]
+    fail_first: u32,
+
+    /// 所有投递都返回 500，用于观察 dead 状态
+    #[arg(long)]
+    always_fail: bool,
+}
+
+#[derive(Clone)]
+struct Receiver {
+    secret: Arc<[u8]>,
+    fail_first: u32,
+    always_fail: bool,
+    // 每个 webhook-id 已收到的次数
+    attempts: Arc<Mutex<HashMap<String, u32>>>,
+}
+
+async fn receive(State(receiver): State<Receiver>, headers: HeaderMap, body: Bytes) -> StatusCode {
+    if let Err(err) = webhook::verify(&receiver.secret, &headers, &body, DEFAULT_TOLERANCE) {
+        eprintln!("rejected: {}", err);
+        return StatusCode::UNAUTHORIZED;
+    }
+
+    let id = headers[WEBHOOK_ID].to_str().unwrap_or_default().to_string();
+    let attempt = {
+        let mut attempts = receiver.attempts.lock().unwrap();
+        let count = attempts.entry(id.clone()).or_default();
+        *count += 1;
+        *count
+    };
+    if receiver.always_fail || attempt <= receiver.fail_first {
+        eprintln!("failing {} (attempt {})", id, attempt);
+        return StatusCode::INTERNAL_SERVER_ERROR;
+    }
+
+    println!("{}", String::from_utf8_lossy(&body));
+    StatusCode::NO_CONTENT
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let cli = Cli::parse();
+    let receiver = Receiver {
+        secret: cli.secret.into_bytes().into(),
+        fail_first: cli.fail_first,
+        always_fail: cli.always_fail,
+        attempts: Arc::default(),
+    };
+    let app = Router::new().fallback(post(receive)).with_state(receiver);
+
+    let listener = tokio::net::TcpListener::bind(cli.bind).await?;
+    eprintln!("webhook 接收端运行在 http://{}", listener.local_addr()?);
+    axum::serve(listener, app).await?;
+    Ok(())
+}
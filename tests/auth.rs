@@ -0,0 +1,127 @@
+use axum::{
+    body::{to_bytes, Body},
+    http::{header, Request, StatusCode},
+    middleware,
+    response::Response,
+    routing::get,
+    Json, Router,
+};
+use chrono::{Duration, Utc};
+use jsonwebtoken::{EncodingKey, Header};
+use serde_json::{json, Value};
+use tower::ServiceExt;
+
+use hello_rust::auth::{require_auth, Claims, JwtAuth, Principal};
+
+const SECRET: &[u8] = b"auth-test-secret-0123456789abcdef";
+
+async fn whoami(principal: Principal) -> Json<Principal> {
+    Json(principal)
+}
+
+fn app() -> Router {
+    Router::new()
+        .route("/whoami", get(whoami))
+        .route_layer(middleware::from_fn_with_state(
+            JwtAuth::new(SECRET),
+            require_auth,
+        ))
+}
+
+fn token(secret: &[u8], expires_in: Duration) -> String {
+    let claims = Claims {
+        sub: "user-1".to_string(),
+        exp: (Utc::now() + expires_in).timestamp() as u64,
+        name: Some("Alice".to_string()),
+        roles: vec!["admin".to_string()],
+    };
+    jsonwebtoken::encode(
+        &Header::default(),
+        &claims,
+        &EncodingKey::from_secret(secret),
+    )
+    .unwrap()
+}
+
+async fn send(authorization: Option<&str>) -> Response {
+    let mut request = Request::builder().uri("/whoami");
+    if let Some(value) = authorization {
+        request = request.header(header::AUTHORIZATION, value);
+    }
+    app()
+        .oneshot(request.body(Body::empty()).unwrap())
+        .await
+        .unwrap()
+}
+
+async fn json_body(response: Response) -> Value {
+    let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    serde_json::from_slice(&bytes).unwrap()
+}
+
+fn challenge(response: &Response) -> &str {
+    response.headers()[header::WWW_AUTHENTICATE]
+        .to_str()
+        .unwrap()
+}
+
+#[tokio::test]
+async fn valid_tokens_expose_the_principal() {
+    let response = send(Some(&format!(
+        "Bearer {}",
+        token(SECRET, Duration::minutes(5))
+    )))
+    .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(response.headers().get(header::WWW_AUTHENTICATE).is_none());
+    assert_eq!(
+        json_body(response).await,
+        json!({ "subject": "user-1", "name": "Alice", "roles": ["admin"] })
+    );
+}
+
+#[tokio::test]
+async fn missing_tokens_get_a_bare_challenge() {
+    let response = send(None).await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    assert_eq!(challenge(&response), r#"Bearer realm="hello_rust""#);
+    assert_eq!(
+        json_body(response).await,
+        json!({ "code": "unauthorized", "message": "Missing bearer token" })
+    );
+}
+
+#[tokio::test]
+async fn invalid_tokens_get_an_invalid_token_challenge() {
+    let forged = format!(
+        "Bearer {}",
+        token(b"some-other-secret", Duration::minutes(5))
+    );
+    let expired = format!("Bearer {}", token(SECRET, Duration::minutes(-5)));
+    for (authorization, message) in [
+        ("Basic dXNlcjpwYXNz", "Malformed Authorization header"),
+        ("Bearer ", "Malformed Authorization header"),
+        ("Bearer not-a-jwt", "Token is invalid"),
+        (forged.as_str(), "Token is invalid"),
+        (expired.as_str(), "Token has expired"),
+    ] {
+        let response = send(Some(authorization)).await;
+        assert_eq!(
+            response.status(),
+            StatusCode::UNAUTHORIZED,
+            "{}",
+            authorization
+        );
+        assert_eq!(
+            challenge(&response),
+            format!(
+                r#"Bearer realm="hello_rust", error="invalid_token", error_description="{}""#,
+                message
+            )
+        );
+        assert_eq!(
+            json_body(response).await,
+            json!({ "code": "invalid_token", "message": message })
+        );
+    }
+}
@@ -0,0 +1,197 @@
+use std::sync::Arc;
+
+use axum::{
+    body::{to_bytes, Body},
+    http::{header, Method, Request, Response, StatusCode},
+    routing::get,
+    Router,
+};
+use serde_json::{json, Value};
+use tokio::sync::{mpsc, Notify};
+use tower::ServiceExt;
+
+use hello_rust::config::{Config, LimitsConfig, Quota, RouteQuota};
+use hello_rust::limits::Limits;
+use hello_rust::user::{user_router, AppState, InMemoryUserStore};
+
+fn limits_config() -> LimitsConfig {
+    let mut config = Config::default().limits;
+    config.default_quota = Quota {
+        burst: 2,
+        per_second: 0.5,
+    };
+    config.routes = vec![RouteQuota {
+        route: "POST /api/users".to_string(),
+        burst: 1,
+        per_second: 0.1,
+    }];
+    config
+}
+
+fn app(config: &LimitsConfig) -> Router {
+    Limits::new(config).apply(user_router(AppState::new(InMemoryUserStore::new())))
+}
+
+async fn send(app: &Router, request: Request<Body>) -> Response<Body> {
+    app.clone().oneshot(request).await.unwrap()
+}
+
+fn get_users(forwarded_for: Option<&str>) -> Request<Body> {
+    let mut request = Request::builder().uri("/api/users");
+    if let Some(ip) = forwarded_for {
+        request = request.header("x-forwarded-for", ip);
+    }
+    request.body(Body::empty()).unwrap()
+}
+
+fn post_user(body: Value) -> Request<Body> {
+    Request::builder()
+        .method(Method::POST)
+        .uri("/api/users")
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(Body::from(body.to_string()))
+        .unwrap()
+}
+
+fn header<'a>(response: &'a Response<Body>, name: &str) -> Option<&'a str> {
+    response
+        .headers()
+        .get(name)
+        .map(|value| value.to_str().unwrap())
+}
+
+async fn json_body(response: Response<Body>) -> Value {
+    let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    serde_json::from_slice(&bytes).unwrap()
+}
+
+#[tokio::test]
+async fn exhausted_quotas_get_429_with_rate_limit_headers() {
+    let mut config = limits_config();
+    config.trust_forwarded_for = true;
+    let app = app(&config);
+    let client = Some("203.0.113.7");
+
+    for remaining in ["1", "0"] {
+        let response = send(&app, get_users(client)).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(header(&response, "ratelimit-limit"), Some("2"));
+        assert_eq!(header(&response, "ratelimit-remaining"), Some(remaining));
+    }
+
+    let limited = send(&app, get_users(client)).await;
+    assert_eq!(limited.status(), StatusCode::TOO_MANY_REQUESTS);
+    assert_eq!(header(&limited, "ratelimit-limit"), Some("2"));
+    assert_eq!(header(&limited, "ratelimit-remaining"), Some("0"));
+    // 每秒补充 0.5 个令牌：2 秒后可以重试，4 秒后回满
+    assert_eq!(header(&limited, "ratelimit-reset"), Some("4"));
+    assert_eq!(header(&limited, "retry-after"), Some("2"));
+    assert_eq!(
+        json_body(limited).await,
+        json!({
+            "code": "rate_limited",
+            "message": "Too many requests, please retry later",
+        })
+    );
+
+    // 其他客户端有各自的桶
+    let other = send(&app, get_users(Some("203.0.113.8"))).await;
+    assert_eq!(other.status(), StatusCode::OK);
+
+    // 单独配置了配额的路由不消耗默认桶
+    let created = send(
+        &app,
+        post_user(json!({ "name": "Limited", "email": "limited@limits.test" })),
+    )
+    .await;
+    assert_eq!(created.status(), StatusCode::OK);
+    assert_eq!(header(&created, "ratelimit-limit"), Some("1"));
+    let again = send(
+        &app,
+        post_user(json!({ "name": "Again", "email": "again@limits.test" })),
+    )
+    .await;
+    assert_eq!(again.status(), StatusCode::TOO_MANY_REQUESTS);
+    assert_eq!(header(&again, "retry-after"), Some("10"));
+}
+
+#[tokio::test]
+async fn requests_over_the_concurrency_limit_are_shed_with_503() {
+    let mut config = limits_config();
+    config.max_concurrent_requests = 1;
+    config.default_quota = Quota {
+        burst: 100,
+        per_second: 100.0,
+    };
+
+    // 处理函数进入后通知测试，并一直占用许可直到 release
+    let (entered_tx, mut entered) = mpsc::channel(1);
+    let release = Arc::new(Notify::new());
+    let held = release.clone();
+    let router = Router::new().route(
+        "/slow",
+        get(move || {
+            let entered = entered_tx.clone();
+            let release = held.clone();
+            async move {
+                entered.send(()).await.unwrap();
+                release.notified().await;
+                "done"
+            }
+        }),
+    );
+    let app = Limits::new(&config).apply(router);
+
+    let slow = || Request::builder().uri("/slow").body(Body::empty()).unwrap();
+    let first = tokio::spawn(app.clone().oneshot(slow()));
+    entered.recv().await.unwrap();
+
+    let shed = send(&app, slow()).await;
+    assert_eq!(shed.status(), StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(
+        json_body(shed).await,
+        json!({
+            "code": "unavailable",
+            "message": "Server is busy, please retry later",
+        })
+    );
+
+    release.notify_one();
+    assert_eq!(first.await.unwrap().unwrap().status(), StatusCode::OK);
+
+    // 许可释放后可以再次处理
+    let next = tokio::spawn(app.clone().oneshot(slow()));
+    entered.recv().await.unwrap();
+    release.notify_one();
+    assert_eq!(next.await.unwrap().unwrap().status(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn bodies_over_the_limit_get_413() {
+    let mut config = limits_config();
+    config.max_body_bytes = 64;
+    let app = app(&config);
+
+    let name = "x".repeat(100);
+    let response = send(
+        &app,
+        post_user(json!({ "name": name, "email": "big@limits.test" })),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+    let body = json_body(response).await;
+    assert_eq!(body["code"], "invalid_request");
+    assert!(body["message"].is_string());
+
+    // 批量导入有单独的上限
+    let row = json!({ "name": name, "email": "bulk@limits.test" });
+    let request = Request::builder()
+        .method(Method::POST)
+        .uri("/api/users:bulk")
+        .header(header::CONTENT_TYPE, "application/x-ndjson")
+        .body(Body::from(format!("{}\n", row)))
+        .unwrap();
+    let response = send(&app, request).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(json_body(response).await["created"], 1);
+}
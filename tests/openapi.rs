@@ -0,0 +1,23 @@
+use std::{env, fs, path::Path};
+
+// 仓库中提交的 openapi.json 须与代码生成的一致；
+// 修改接口后以 UPDATE_OPENAPI=1 cargo test --test openapi 重新生成
+#[test]
+fn committed_spec_matches_code() {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("openapi.json");
+    let generated = hello_rust::user::openapi()
+        .to_pretty_json()
+        .expect("OpenAPI document is always serializable")
+        + "\n";
+
+    if env::var_os("UPDATE_OPENAPI").is_some() {
+        fs::write(&path, &generated).expect("failed to write openapi.json");
+        return;
+    }
+
+    let committed = fs::read_to_string(&path).unwrap_or_default();
+    assert!(
+        committed == generated,
+        "openapi.json is out of date, run `UPDATE_OPENAPI=1 cargo test --test openapi` and commit the result"
+    );
+}
@@ -0,0 +1,88 @@
+use std::time::Duration;
+
+use axum::{
+    body::{to_bytes, Body},
+    extract::Path,
+    http::{Method, Request, StatusCode},
+    routing::get,
+    Router,
+};
+use serde_json::{json, Value};
+use tower::ServiceExt;
+
+use hello_rust::config::{RouteTimeout, TimeoutConfig};
+use hello_rust::timeout::Timeouts;
+
+// 处理函数等待路径中给定的毫秒数后返回
+async fn sleep(Path(millis): Path<u64>) -> &'static str {
+    tokio::time::sleep(Duration::from_millis(millis)).await;
+    "done"
+}
+
+fn app() -> Router {
+    let config = TimeoutConfig {
+        request_ms: 100,
+        routes: vec![
+            RouteTimeout {
+                route: "GET /strict/{millis}".to_string(),
+                request_ms: 20,
+            },
+            RouteTimeout {
+                route: "GET /patient/{millis}".to_string(),
+                request_ms: 2000,
+            },
+        ],
+    };
+    let router = Router::new()
+        .route("/default/{millis}", get(sleep))
+        .route("/strict/{millis}", get(sleep))
+        .route("/patient/{millis}", get(sleep).post(sleep));
+    Timeouts::new(&config).apply(router)
+}
+
+async fn send(method: Method, uri: &str) -> (StatusCode, Value) {
+    let request = Request::builder()
+        .method(method)
+        .uri(uri)
+        .body(Body::empty())
+        .unwrap();
+    let response = app().oneshot(request).await.unwrap();
+    let status = response.status();
+    let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let body = serde_json::from_slice(&bytes).unwrap_or(Value::Null);
+    (status, body)
+}
+
+#[tokio::test]
+async fn slow_handlers_get_504_with_the_error_body() {
+    assert_eq!(send(Method::GET, "/default/10").await.0, StatusCode::OK);
+
+    let (status, body) = send(Method::GET, "/default/500").await;
+    assert_eq!(status, StatusCode::GATEWAY_TIMEOUT);
+    assert_eq!(
+        body,
+        json!({
+            "code": "timeout",
+            "message": "The request did not complete within 100 ms",
+        })
+    );
+}
+
+#[tokio::test]
+async fn route_overrides_keep_their_own_timeout() {
+    // 比默认值短的覆盖
+    let (status, body) = send(Method::GET, "/strict/60").await;
+    assert_eq!(status, StatusCode::GATEWAY_TIMEOUT);
+    assert_eq!(body["message"], "The request did not complete within 20 ms");
+
+    // 比默认值长的覆盖
+    assert_eq!(send(Method::GET, "/patient/300").await.0, StatusCode::OK);
+
+    // 覆盖按方法和路由模板匹配，同一路径的其他方法仍用默认值
+    let (status, body) = send(Method::POST, "/patient/300").await;
+    assert_eq!(status, StatusCode::GATEWAY_TIMEOUT);
+    assert_eq!(
+        body["message"],
+        "The request did not complete within 100 ms"
+    );
+}
@@ -0,0 +1,87 @@
+use std::env;
+
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use hello_rust::migrate;
+use hello_rust::user::{AuditContext, CreateUserRequest, Precondition, UserRepository, UserStore};
+
+// 需要 Postgres：设置 DATABASE_URL 时运行
+async fn repository() -> Option<UserRepository> {
+    let Ok(url) = env::var("DATABASE_URL") else {
+        eprintln!("DATABASE_URL is not set, skipping the unit of work test");
+        return None;
+    };
+    let pool = PgPool::connect(&url)
+        .await
+        .expect("failed to connect to DATABASE_URL");
+    migrate::up(&pool).await.expect("failed to run migrations");
+    Some(UserRepository::new(pool))
+}
+
+fn input(name: &str) -> CreateUserRequest {
+    CreateUserRequest {
+        name: name.to_string(),
+        email: format!("{}@{}.uow.test", name, Uuid::new_v4().simple()),
+    }
+}
+
+#[tokio::test]
+async fn callers_share_the_transaction_through_the_connection() {
+    let Some(repository) = repository().await else {
+        return;
+    };
+    let ctx = AuditContext::system("uow-test");
+
+    let mut uow = repository.begin().await.unwrap();
+    let isolation: String = sqlx::query_scalar("SELECT current_setting('transaction_isolation')")
+        .fetch_one(uow.connection())
+        .await
+        .unwrap();
+    assert_eq!(isolation, "repeatable read");
+
+    let (user, _) = uow.create_user(&input("rollback"), &ctx).await.unwrap();
+    let visible: i64 = sqlx::query_scalar("SELECT count(*) FROM users WHERE id = $1")
+        .bind(user.id)
+        .fetch_one(uow.connection())
+        .await
+        .unwrap();
+    assert_eq!(visible, 1);
+
+    // 未提交的事务在 drop 时回滚，用户和其他语句的修改一起撤销
+    drop(uow);
+    assert!(repository.get_user(user.id).await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn concurrent_updates_fail_with_a_retryable_serialization_error() {
+    let Some(repository) = repository().await else {
+        return;
+    };
+    let ctx = AuditContext::system("uow-test");
+    let (user, _) = repository.create_user(&input("racer"), &ctx).await.unwrap();
+
+    // 第一条语句确定快照，之后其他事务提交的修改对本事务不可见
+    let mut stale = repository.begin().await.unwrap();
+    stale.get_user(user.id).await.unwrap().unwrap();
+
+    let renamed = CreateUserRequest {
+        name: "renamed".to_string(),
+        ..input("racer")
+    };
+    repository
+        .replace_user(user.id, &renamed, &Precondition::Any, &ctx)
+        .await
+        .unwrap()
+        .unwrap();
+
+    let conflicting = CreateUserRequest {
+        name: "conflicting".to_string(),
+        ..input("racer")
+    };
+    let err = stale
+        .replace_user(user.id, &conflicting, &Precondition::Any, &ctx)
+        .await
+        .unwrap_err();
+    assert!(err.is_retryable(), "{}", err);
+}
@@ -0,0 +1,766 @@
+use std::env;
+
+use axum::{
+    body::{to_bytes, Body},
+    http::{header, Method, Request, StatusCode},
+    middleware, Router,
+};
+use chrono::{Duration, Utc};
+use jsonwebtoken::{EncodingKey, Header};
+use serde_json::{json, Value};
+use sqlx::PgPool;
+use tower::ServiceExt;
+use uuid::Uuid;
+
+use hello_rust::auth::{require_auth, Claims, JwtAuth};
+use hello_rust::migrate;
+use hello_rust::user::{
+    admin_router, user_router, AppState, InMemoryUserStore, UserRepository, UserStore, ADMIN_ROLE,
+    EXPORT_PAGE_SIZE, IMPORT_BATCH_SIZE, JSON_PATCH, MAX_IMPORT_ROWS, MERGE_PATCH,
+};
+
+const JWT_SECRET: &[u8] = b"contract-test-secret";
+
+// 两个存储实现经由同一套路由须给出相同的响应：用例先对内存实现运行，
+// 设置 DATABASE_URL 时再对 Postgres 实现运行（会执行迁移，并写入随机域名下的用户；
+// 清除用例的保留期为 0，会清除库中所有已软删除的用户）
+#[tokio::test]
+async fn in_memory_store_honours_the_contract() {
+    run_contract(InMemoryUserStore::new()).await;
+}
+
+#[tokio::test]
+async fn postgres_store_honours_the_contract() {
+    let Ok(url) = env::var("DATABASE_URL") else {
+        eprintln!("DATABASE_URL is not set, skipping the Postgres contract run");
+        return;
+    };
+    let pool = PgPool::connect(&url)
+        .await
+        .expect("failed to connect to DATABASE_URL");
+    migrate::up(&pool).await.expect("failed to run migrations");
+    run_contract(UserRepository::new(pool)).await;
+}
+
+async fn run_contract<S: UserStore>(store: S) {
+    // 管理路由和服务端一样挂载认证中间件
+    let admin =
+        admin_router(AppState::new(store.clone()).purge_retention(Duration::zero())).route_layer(
+            middleware::from_fn_with_state(JwtAuth::new(JWT_SECRET), require_auth),
+        );
+    let api = Api {
+        app: user_router(AppState::new(store.clone())).merge(admin),
+        // 每次运行使用独立的邮箱域名，数据库中已有的数据不影响结果
+        run: Uuid::new_v4().simple().to_string(),
+    };
+    crud(&api).await;
+    error_codes(&api).await;
+    conditional_requests(&api).await;
+    strict_if_match(&api, store).await;
+    patch(&api).await;
+    pagination(&api).await;
+    soft_delete_and_restore(&api).await;
+    audit_history(&api).await;
+    bulk_import_and_export(&api).await;
+}
+
+struct Api {
+    app: Router,
+    run: String,
+}
+
+struct Reply {
+    status: StatusCode,
+    etag: Option<String>,
+    body: Value,
+}
+
+impl Reply {
+    fn code(&self) -> &str {
+        self.body["code"].as_str().unwrap_or_default()
+    }
+
+    // 错误响应中 fields 的字段名
+    fn fields(&self) -> Vec<&str> {
+        self.body["fields"]
+            .as_array()
+            .map(|fields| {
+                fields
+                    .iter()
+                    .filter_map(|field| field["field"].as_str())
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+}
+
+impl Api {
+    fn domain(&self, section: &str) -> String {
+        format!("{}.{}.test", section, self.run)
+    }
+
+    async fn send(
+        &self,
+        method: Method,
+        uri: &str,
+        body: Option<(&str, Value)>,
+        if_match: Option<&str>,
+    ) -> Reply {
+        let mut request = Request::builder().method(method).uri(uri);
+        if let Some(if_match) = if_match {
+            request = request.header(header::IF_MATCH, if_match);
+        }
+        let request = match body {
+            Some((content_type, body)) => request
+                .header(header::CONTENT_TYPE, content_type)
+                .body(Body::from(body.to_string())),
+            None => request.body(Body::empty()),
+        }
+        .unwrap();
+        self.dispatch(request).await
+    }
+
+    // 携带 subject 签发、拥有 roles 角色的令牌发送请求
+    async fn send_as(&self, method: Method, uri: &str, subject: &str, roles: &[&str]) -> Reply {
+        let claims = Claims {
+            sub: subject.to_string(),
+            exp: (Utc::now() + Duration::minutes(5)).timestamp() as u64,
+            name: None,
+            roles: roles.iter().map(|role| role.to_string()).collect(),
+        };
+        let token = jsonwebtoken::encode(
+            &Header::default(),
+            &claims,
+            &EncodingKey::from_secret(JWT_SECRET),
+        )
+        .unwrap();
+        let request = Request::builder()
+            .method(method)
+            .uri(uri)
+            .header(header::AUTHORIZATION, format!("Bearer {}", token))
+            .body(Body::empty())
+            .unwrap();
+        self.dispatch(request).await
+    }
+
+    // 批量导入，请求体为原始的 NDJSON 或 CSV
+    async fn import(&self, content_type: &str, body: String) -> Reply {
+        let request = Request::builder()
+            .method(Method::POST)
+            .uri("/api/users:bulk")
+            .header(header::CONTENT_TYPE, content_type)
+            .body(Body::from(body))
+            .unwrap();
+        self.dispatch(request).await
+    }
+
+    // 导出的响应体文本
+    async fn export(&self, query: &str) -> String {
+        let request = Request::builder()
+            .uri(format!("/api/users:export?{}", query))
+            .body(Body::empty())
+            .unwrap();
+        let response = self.app.clone().oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        String::from_utf8(bytes.to_vec()).unwrap()
+    }
+
+    async fn dispatch(&self, request: Request<Body>) -> Reply {
+        let response = self.app.clone().oneshot(request).await.unwrap();
+        let status = response.status();
+        let etag = response
+            .headers()
+            .get(header::ETAG)
+            .map(|value| value.to_str().unwrap().to_string());
+        let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let body = if bytes.is_empty() {
+            Value::Null
+        } else {
+            serde_json::from_slice(&bytes).unwrap()
+        };
+        Reply { status, etag, body }
+    }
+
+    async fn get(&self, uri: &str) -> Reply {
+        self.send(Method::GET, uri, None, None).await
+    }
+
+    async fn post(&self, uri: &str, body: Value) -> Reply {
+        self.send(Method::POST, uri, Some(("application/json", body)), None)
+            .await
+    }
+
+    async fn put(&self, uri: &str, body: Value, if_match: Option<&str>) -> Reply {
+        self.send(Method::PUT, uri, Some(("application/json", body)), if_match)
+            .await
+    }
+
+    async fn patch(
+        &self,
+        uri: &str,
+        content_type: &str,
+        body: Value,
+        if_match: Option<&str>,
+    ) -> Reply {
+        self.send(Method::PATCH, uri, Some((content_type, body)), if_match)
+            .await
+    }
+
+    async fn delete(&self, uri: &str, if_match: Option<&str>) -> Reply {
+        self.send(Method::DELETE, uri, None, if_match).await
+    }
+
+    // 创建用户并返回其 id
+    async fn create(&self, name: &str, email: &str) -> i64 {
+        let reply = self
+            .post("/api/users", json!({ "name": name, "email": email }))
+            .await;
+        assert_eq!(
+            reply.status,
+            StatusCode::OK,
+            "create failed: {}",
+            reply.body
+        );
+        reply.body["id"].as_i64().unwrap()
+    }
+}
+
+async fn crud(api: &Api) {
+    let email = format!("alice@{}", api.domain("crud"));
+    let created = api
+        .post("/api/users", json!({ "name": "  Alice  ", "email": email }))
+        .await;
+    assert_eq!(created.status, StatusCode::OK);
+    assert_eq!(created.etag.as_deref(), Some("\"1\""));
+    assert_eq!(created.body["name"], "Alice");
+    assert_eq!(created.body["email"], email.as_str());
+    assert_eq!(created.body["version"], 1);
+    let uri = format!("/api/users/{}", created.body["id"]);
+
+    let fetched = api.get(&uri).await;
+    assert_eq!(fetched.status, StatusCode::OK);
+    assert_eq!(fetched.etag.as_deref(), Some("\"1\""));
+    assert_eq!(fetched.body, created.body);
+
+    let replaced = api
+        .put(&uri, json!({ "name": "Alice Smith", "email": email }), None)
+        .await;
+    assert_eq!(replaced.status, StatusCode::OK);
+    assert_eq!(replaced.etag.as_deref(), Some("\"2\""));
+    assert_eq!(replaced.body["name"], "Alice Smith");
+    assert_eq!(replaced.body["version"], 2);
+    assert_eq!(replaced.body["created_at"], created.body["created_at"]);
+    assert_eq!(api.get(&uri).await.body, replaced.body);
+
+    assert_eq!(api.delete(&uri, None).await.status, StatusCode::NO_CONTENT);
+    let gone = api.get(&uri).await;
+    assert_eq!(gone.status, StatusCode::NOT_FOUND);
+    assert_eq!(gone.code(), "not_found");
+}
+
+async fn error_codes(api: &Api) {
+    let domain = api.domain("errors");
+    let taken = format!("taken@{}", domain);
+    let first = api.create("First", &taken).await;
+    let second = api.create("Second", &format!("second@{}", domain)).await;
+
+    let duplicate = api
+        .post("/api/users", json!({ "name": "Copy", "email": taken }))
+        .await;
+    assert_eq!(duplicate.status, StatusCode::CONFLICT);
+    assert_eq!(duplicate.code(), "conflict");
+    assert_eq!(duplicate.fields(), ["email"]);
+
+    let replaced = api
+        .put(
+            &format!("/api/users/{}", second),
+            json!({ "name": "Second", "email": taken }),
+            None,
+        )
+        .await;
+    assert_eq!(replaced.status, StatusCode::CONFLICT);
+    assert_eq!(replaced.fields(), ["email"]);
+
+    // 所有不合法的字段一次返回
+    let invalid = api
+        .post(
+            "/api/users",
+            json!({ "name": " ", "email": "not-an-email" }),
+        )
+        .await;
+    assert_eq!(invalid.status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(invalid.code(), "validation_failed");
+    let mut fields = invalid.fields();
+    fields.sort_unstable();
+    assert_eq!(fields, ["email", "name"]);
+
+    let missing = api.post("/api/users", json!({ "email": 42 })).await;
+    assert_eq!(missing.status, StatusCode::UNPROCESSABLE_ENTITY);
+    let mut fields = missing.fields();
+    fields.sort_unstable();
+    assert_eq!(fields, ["email", "name"]);
+
+    let missing_user = api.get("/api/users/2147483000").await;
+    assert_eq!(missing_user.status, StatusCode::NOT_FOUND);
+    assert_eq!(missing_user.code(), "not_found");
+    let body = json!({ "name": "Nobody", "email": format!("nobody@{}", domain) });
+    let missing_user = api.put("/api/users/2147483000", body, None).await;
+    assert_eq!(missing_user.status, StatusCode::NOT_FOUND);
+    let missing_user = api.delete("/api/users/2147483000", None).await;
+    assert_eq!(missing_user.status, StatusCode::NOT_FOUND);
+    assert_eq!(missing_user.code(), "not_found");
+
+    let bad_limit = api.get("/api/users?limit=0").await;
+    assert_eq!(bad_limit.status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(bad_limit.fields(), ["limit"]);
+
+    // 冲突的写入不改变已有用户
+    assert_eq!(
+        api.get(&format!("/api/users/{}", first)).await.body["version"],
+        1
+    );
+}
+
+async fn conditional_requests(api: &Api) {
+    let email = format!("etag@{}", api.domain("etag"));
+    let id = api.create("Etag", &email).await;
+    let uri = format!("/api/users/{}", id);
+    let body = json!({ "name": "Etag 2", "email": email });
+
+    let stale = api.put(&uri, body.clone(), Some("\"2\"")).await;
+    assert_eq!(stale.status, StatusCode::PRECONDITION_FAILED);
+    assert_eq!(stale.code(), "precondition_failed");
+
+    let current = api.put(&uri, body.clone(), Some("\"7\", \"1\"")).await;
+    assert_eq!(current.status, StatusCode::OK);
+    assert_eq!(current.etag.as_deref(), Some("\"2\""));
+
+    let stale = api.delete(&uri, Some("\"1\"")).await;
+    assert_eq!(stale.status, StatusCode::PRECONDITION_FAILED);
+    assert_eq!(api.get(&uri).await.status, StatusCode::OK);
+
+    assert_eq!(
+        api.delete(&uri, Some("*")).await.status,
+        StatusCode::NO_CONTENT
+    );
+}
+
+// 严格模式下写操作必须携带 If-Match
+async fn strict_if_match<S: UserStore>(api: &Api, store: S) {
+    let strict = Api {
+        app: user_router(AppState::new(store).require_if_match(true)),
+        run: api.run.clone(),
+    };
+    let email = format!("strict@{}", api.domain("strict"));
+    let id = strict.create("Strict", &email).await;
+    let uri = format!("/api/users/{}", id);
+    let body = json!({ "name": "Strict 2", "email": email });
+
+    let unconditional = strict.put(&uri, body.clone(), None).await;
+    assert_eq!(unconditional.status, StatusCode::PRECONDITION_REQUIRED);
+    assert_eq!(unconditional.code(), "precondition_required");
+    let unconditional = strict.delete(&uri, None).await;
+    assert_eq!(unconditional.status, StatusCode::PRECONDITION_REQUIRED);
+
+    assert_eq!(
+        strict.put(&uri, body, Some("\"1\"")).await.status,
+        StatusCode::OK
+    );
+    assert_eq!(
+        strict.delete(&uri, Some("\"2\"")).await.status,
+        StatusCode::NO_CONTENT
+    );
+}
+
+async fn patch(api: &Api) {
+    let domain = api.domain("patch");
+    let email = format!("patch@{}", domain);
+    let id = api.create("Patch", &email).await;
+    let uri = format!("/api/users/{}", id);
+
+    let merged = api
+        .patch(&uri, MERGE_PATCH, json!({ "name": "Merged" }), None)
+        .await;
+    assert_eq!(merged.status, StatusCode::OK);
+    assert_eq!(merged.body["name"], "Merged");
+    assert_eq!(merged.body["email"], email.as_str());
+    assert_eq!(merged.etag.as_deref(), Some("\"2\""));
+
+    let new_email = format!("patched@{}", domain);
+    let operations = json!([
+        { "op": "test", "path": "/name", "value": "Merged" },
+        { "op": "replace", "path": "/email", "value": new_email },
+    ]);
+    let patched = api.patch(&uri, JSON_PATCH, operations, Some("\"2\"")).await;
+    assert_eq!(patched.status, StatusCode::OK);
+    assert_eq!(patched.body["email"], new_email.as_str());
+    assert_eq!(patched.body["version"], 3);
+
+    let stale = api
+        .patch(&uri, MERGE_PATCH, json!({ "name": "Stale" }), Some("\"2\""))
+        .await;
+    assert_eq!(stale.status, StatusCode::PRECONDITION_FAILED);
+
+    let cleared = api
+        .patch(&uri, MERGE_PATCH, json!({ "name": null }), None)
+        .await;
+    assert_eq!(cleared.status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(cleared.fields(), ["name"]);
+
+    let unsupported = api
+        .patch(&uri, "text/plain", json!({ "name": "Text" }), None)
+        .await;
+    assert_eq!(unsupported.status, StatusCode::UNSUPPORTED_MEDIA_TYPE);
+
+    let missing = api
+        .patch(
+            "/api/users/2147483000",
+            MERGE_PATCH,
+            json!({ "name": "Nobody" }),
+            None,
+        )
+        .await;
+    assert_eq!(missing.status, StatusCode::NOT_FOUND);
+
+    // 失败的补丁不改变用户
+    assert_eq!(api.get(&uri).await.body, patched.body);
+}
+
+// 沿 next_cursor 翻页，返回各页的姓名
+async fn collect_pages(api: &Api, query: &str) -> Vec<Vec<String>> {
+    let mut pages = Vec::new();
+    let mut uri = format!("/api/users?{}", query);
+    loop {
+        let reply = api.get(&uri).await;
+        assert_eq!(reply.status, StatusCode::OK, "{}", reply.body);
+        let names = reply.body["items"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|user| user["name"].as_str().unwrap().to_string())
+            .collect();
+        pages.push(names);
+        match reply.body["next_cursor"].as_str() {
+            Some(cursor) => uri = format!("/api/users?{}&after={}", query, cursor),
+            None => return pages,
+        }
+    }
+}
+
+async fn pagination(api: &Api) {
+    let domain = api.domain("pages");
+    for name in ["page-c", "page-a", "page-e", "page-b", "page-d"] {
+        api.create(name, &format!("{}@{}", name, domain)).await;
+    }
+
+    let pages = collect_pages(api, &format!("email_domain={}&sort=name&limit=2", domain)).await;
+    assert_eq!(
+        pages,
+        [
+            vec!["page-a", "page-b"],
+            vec!["page-c", "page-d"],
+            vec!["page-e"],
+        ]
+    );
+
+    let pages = collect_pages(api, &format!("email_domain={}&sort=-name&limit=3", domain)).await;
+    assert_eq!(
+        pages,
+        [vec!["page-e", "page-d", "page-c"], vec!["page-b", "page-a"]]
+    );
+
+    // 默认按创建时间倒序
+    let pages = collect_pages(api, &format!("email_domain={}&limit=5", domain)).await;
+    assert_eq!(pages.len(), 1);
+    assert_eq!(pages[0].first().map(String::as_str), Some("page-d"));
+    assert_eq!(pages[0].last().map(String::as_str), Some("page-c"));
+
+    let first = api
+        .get(&format!(
+            "/api/users?email_domain={}&sort=name&limit=1",
+            domain
+        ))
+        .await;
+    let cursor = first.body["next_cursor"].as_str().unwrap();
+    let mismatched = api
+        .get(&format!(
+            "/api/users?email_domain={}&sort=id&after={}",
+            domain, cursor
+        ))
+        .await;
+    assert_eq!(mismatched.status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(mismatched.fields(), ["after"]);
+
+    let garbage = api.get("/api/users?after=not-a-cursor").await;
+    assert_eq!(garbage.status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(garbage.fields(), ["after"]);
+}
+
+async fn soft_delete_and_restore(api: &Api) {
+    let domain = api.domain("restore");
+    let email = format!("restore@{}", domain);
+    let id = api.create("Restore", &email).await;
+    let uri = format!("/api/users/{}", id);
+    let listing = format!("/api/users?email_domain={}", domain);
+
+    assert_eq!(api.delete(&uri, None).await.status, StatusCode::NO_CONTENT);
+    assert_eq!(api.get(&uri).await.status, StatusCode::NOT_FOUND);
+    assert_eq!(api.get(&listing).await.body["items"], json!([]));
+    assert_eq!(api.delete(&uri, None).await.status, StatusCode::NOT_FOUND);
+
+    let restored = api.post(&format!("{}/restore", uri), json!({})).await;
+    assert_eq!(restored.status, StatusCode::OK);
+    assert_eq!(restored.body["version"], 3);
+    assert_eq!(restored.etag.as_deref(), Some("\"3\""));
+    assert_eq!(api.get(&uri).await.body, restored.body);
+    assert_eq!(
+        api.get(&listing).await.body["items"]
+            .as_array()
+            .map(Vec::len),
+        Some(1)
+    );
+
+    let again = api.post(&format!("{}/restore", uri), json!({})).await;
+    assert_eq!(again.status, StatusCode::NOT_FOUND);
+    assert_eq!(again.code(), "not_found");
+
+    // 软删除后邮箱可被新用户使用，此时不能再恢复旧用户
+    assert_eq!(api.delete(&uri, None).await.status, StatusCode::NO_CONTENT);
+    api.create("Replacement", &email).await;
+    let conflict = api.post(&format!("{}/restore", uri), json!({})).await;
+    assert_eq!(conflict.status, StatusCode::CONFLICT);
+    assert_eq!(conflict.fields(), ["email"]);
+    assert_eq!(api.get(&uri).await.status, StatusCode::NOT_FOUND);
+}
+
+// 沿 next_cursor 翻页，返回各页审计记录的 action
+async fn collect_history(api: &Api, uri: &str, limit: u32) -> Vec<Vec<Value>> {
+    let mut pages = Vec::new();
+    let mut page_uri = format!("{}?limit={}", uri, limit);
+    loop {
+        let reply = api
+            .send_as(Method::GET, &page_uri, "auditor", &[ADMIN_ROLE])
+            .await;
+        assert_eq!(reply.status, StatusCode::OK, "{}", reply.body);
+        pages.push(reply.body["items"].as_array().unwrap().clone());
+        match reply.body["next_cursor"].as_str() {
+            Some(cursor) => page_uri = format!("{}?limit={}&after={}", uri, limit, cursor),
+            None => return pages,
+        }
+    }
+}
+
+async fn audit_history(api: &Api) {
+    let domain = api.domain("audit");
+    let email = format!("audit@{}", domain);
+    let id = api.create("Audit", &email).await;
+    let uri = format!("/api/users/{}", id);
+    let history = format!("/api/admin/users/{}/history", id);
+
+    // 历史中包含姓名和邮箱，仅限 admin 角色
+    let anonymous = api.get(&history).await;
+    assert_eq!(anonymous.status, StatusCode::UNAUTHORIZED);
+    assert_eq!(anonymous.code(), "unauthorized");
+    let viewer = api.send_as(Method::GET, &history, "viewer", &[]).await;
+    assert_eq!(viewer.status, StatusCode::FORBIDDEN);
+    assert_eq!(
+        api.get(&format!("{}/history", uri)).await.status,
+        StatusCode::NOT_FOUND
+    );
+
+    let replaced = api
+        .put(&uri, json!({ "name": "Audit Smith", "email": email }), None)
+        .await;
+    assert_eq!(replaced.status, StatusCode::OK);
+    assert_eq!(api.delete(&uri, None).await.status, StatusCode::NO_CONTENT);
+    let restored = api.post(&format!("{}/restore", uri), json!({})).await;
+    assert_eq!(restored.status, StatusCode::OK);
+    assert_eq!(api.delete(&uri, None).await.status, StatusCode::NO_CONTENT);
+    let purged = api
+        .send_as(
+            Method::POST,
+            "/api/admin/users:purge",
+            "janitor",
+            &[ADMIN_ROLE],
+        )
+        .await;
+    assert_eq!(purged.status, StatusCode::OK, "{}", purged.body);
+    assert!(purged.body["purged"].as_u64().unwrap() >= 1);
+
+    // 按 id 倒序，清除后仍可查询
+    let pages = collect_history(api, &history, 2).await;
+    let actions: Vec<Vec<&str>> = pages
+        .iter()
+        .map(|page| {
+            page.iter()
+                .map(|entry| entry["action"].as_str().unwrap())
+                .collect()
+        })
+        .collect();
+    assert_eq!(
+        actions,
+        [
+            vec!["purge", "delete"],
+            vec!["restore", "delete"],
+            vec!["replace", "create"],
+        ]
+    );
+    let entries: Vec<&Value> = pages.iter().flatten().collect();
+    let ids: Vec<i64> = entries
+        .iter()
+        .map(|entry| entry["id"].as_i64().unwrap())
+        .collect();
+    assert!(ids.windows(2).all(|pair| pair[0] > pair[1]));
+    assert!(entries.iter().all(|entry| entry["user_id"] == id));
+
+    let [purge, delete, restore, _, replace, create] = entries[..] else {
+        panic!("unexpected history: {:?}", entries);
+    };
+    // 用户路由未认证，actor 为空；清除由调用管理接口的主体发起
+    assert_eq!(create["actor"], Value::Null);
+    assert_eq!(purge["actor"], "janitor");
+
+    assert_eq!(create["before"], Value::Null);
+    assert_eq!(create["after"], json!({ "name": "Audit", "email": email }));
+    // before/after 只保留变化的字段
+    assert_eq!(replace["before"], json!({ "name": "Audit" }));
+    assert_eq!(replace["after"], json!({ "name": "Audit Smith" }));
+    assert_eq!(delete["before"], json!({ "deleted_at": null }));
+    assert!(delete["after"]["deleted_at"].is_string());
+    assert!(restore["before"]["deleted_at"].is_string());
+    assert_eq!(restore["after"], json!({ "deleted_at": null }));
+    assert_eq!(purge["before"]["email"], email.as_str());
+    assert!(purge["before"]["deleted_at"].is_string());
+    assert_eq!(purge["after"], Value::Null);
+
+    let single = collect_history(api, &history, 6).await;
+    assert_eq!(single.len(), 1);
+    assert_eq!(single[0].len(), 6);
+
+    for query in ["limit=0", "limit=201", "after=not-a-cursor"] {
+        let invalid = api
+            .send_as(
+                Method::GET,
+                &format!("{}?{}", history, query),
+                "auditor",
+                &[ADMIN_ROLE],
+            )
+            .await;
+        assert_eq!(
+            invalid.status,
+            StatusCode::UNPROCESSABLE_ENTITY,
+            "{}",
+            query
+        );
+    }
+}
+
+async fn bulk_import_and_export(api: &Api) {
+    let domain = api.domain("bulk");
+    // 超过一个写入批次，导出时也超过两页
+    let rows = IMPORT_BATCH_SIZE.max(2 * EXPORT_PAGE_SIZE as usize) + 100;
+    let line = |name: String, email: String| json!({ "name": name, "email": email }).to_string();
+    let mut lines: Vec<String> = (1..=rows)
+        .map(|row| {
+            line(
+                format!("bulk-{:05}", row),
+                format!("bulk-{}@{}", row, domain),
+            )
+        })
+        .collect();
+    // 同一批次内重复的邮箱；与第一个批次重复的邮箱落在后续批次中
+    lines[2] = line("dup".to_string(), format!("bulk-2@{}", domain));
+    lines[rows - 1] = line("late-dup".to_string(), format!("bulk-5@{}", domain));
+    lines[6] = line(String::new(), "not-an-email".to_string());
+    lines[8] = "not json".to_string();
+    lines[10] = json!({ "name": "extra", "email": format!("extra@{}", domain), "role": "admin" })
+        .to_string();
+    lines[12] = String::new();
+
+    let report = api.import("application/x-ndjson", lines.join("\n")).await;
+    assert_eq!(report.status, StatusCode::OK, "{}", report.body);
+    let failed = [3, 7, 9, 11, rows];
+    assert_eq!(report.body["total"], rows - 1);
+    assert_eq!(report.body["created"], rows - 1 - failed.len());
+    assert_eq!(report.body["failed"], failed.len());
+
+    // 每个非空行一条结果，按行号排列
+    let results = report.body["results"].as_array().unwrap();
+    let numbers: Vec<usize> = results
+        .iter()
+        .map(|result| result["row"].as_u64().unwrap() as usize)
+        .collect();
+    let expected: Vec<usize> = (1..=rows).filter(|&row| row != 13).collect();
+    assert_eq!(numbers, expected);
+    let result = |row: usize| &results[expected.iter().position(|&r| r == row).unwrap()];
+
+    for row in [1, 2, 5, 1000, rows - 1] {
+        assert_eq!(result(row)["status"], "created", "row {}", row);
+        assert!(result(row)["id"].is_i64());
+    }
+    let failure = |row: usize| {
+        let result = result(row);
+        assert_eq!(result["status"], "failed", "row {}", row);
+        let fields: Vec<&str> = result["error"]["fields"]
+            .as_array()
+            .map(|fields| {
+                fields
+                    .iter()
+                    .filter_map(|field| field["field"].as_str())
+                    .collect()
+            })
+            .unwrap_or_default();
+        (
+            result["error"]["code"].as_str().unwrap().to_string(),
+            fields,
+        )
+    };
+    assert_eq!(failure(3), ("conflict".to_string(), vec!["email"]));
+    assert_eq!(failure(rows), ("conflict".to_string(), vec!["email"]));
+    assert_eq!(
+        failure(7),
+        ("validation_failed".to_string(), vec!["email", "name"])
+    );
+    assert_eq!(failure(9).0, "invalid_request");
+    assert_eq!(failure(11), ("validation_failed".to_string(), vec!["role"]));
+
+    // 逐页读取的导出结果按 id 升序，且不重复、不遗漏
+    let exported = api.export(&format!("email_domain={}", domain)).await;
+    let users: Vec<Value> = exported
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert_eq!(users.len(), rows - 1 - failed.len());
+    let ids: Vec<i64> = users
+        .iter()
+        .map(|user| user["id"].as_i64().unwrap())
+        .collect();
+    assert!(ids.windows(2).all(|pair| pair[0] < pair[1]));
+    assert_eq!(users[0]["name"], "bulk-00001");
+    assert_eq!(
+        users.last().unwrap()["name"],
+        format!("bulk-{:05}", rows - 1)
+    );
+
+    let csv = api
+        .export(&format!("format=csv&email_domain={}", domain))
+        .await;
+    let csv_lines: Vec<&str> = csv.lines().collect();
+    assert_eq!(csv_lines.len(), users.len() + 1);
+    assert_eq!(csv_lines[0], "id,name,email,created_at,updated_at,version");
+    assert!(csv_lines[1..].iter().all(|line| !line.starts_with("id,")));
+
+    // 行数超过上限时整个请求被拒绝，不写入任何行
+    let too_many = api
+        .import("application/x-ndjson", "{}\n".repeat(MAX_IMPORT_ROWS + 1))
+        .await;
+    assert_eq!(too_many.status, StatusCode::PAYLOAD_TOO_LARGE);
+    assert_eq!(too_many.code(), "invalid_request");
+
+    let missing = api
+        .import("text/csv", format!("name\ncsv-only@{}\n", domain))
+        .await;
+    assert_eq!(missing.status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(missing.fields(), ["email"]);
+}
@@ -0,0 +1,342 @@
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use axum::{
+    body::{Body, BodyDataStream},
+    http::{header, Method, Request, StatusCode},
+    Router,
+};
+use chrono::{DateTime, Utc};
+use futures::StreamExt;
+use serde_json::{json, Value};
+use tokio::net::{TcpListener, TcpStream};
+use tokio_tungstenite::{tungstenite::Message, MaybeTlsStream, WebSocketStream};
+use tower::ServiceExt;
+
+use hello_rust::user::{
+    user_router, AppState, AuditContext, AuditEntry, CreateOutcome, CreateUserRequest,
+    HistoryQuery, InMemoryUserStore, Precondition, StoreError, User, UserEvent, UserQuery,
+    UserStore,
+};
+
+// 内存存储的包装，按顺序记录写操作返回的事件，用于核对推送出去的事件 id
+#[derive(Clone, Default)]
+struct RecordingStore {
+    inner: InMemoryUserStore,
+    events: Arc<Mutex<Vec<UserEvent>>>,
+}
+
+impl RecordingStore {
+    fn record(&self, event: &UserEvent) {
+        self.events.lock().unwrap().push(event.clone());
+    }
+
+    fn event_ids(&self) -> Vec<String> {
+        self.events
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|event| event.id.to_string())
+            .collect()
+    }
+}
+
+impl UserStore for RecordingStore {
+    async fn create_user(
+        &self,
+        input: &CreateUserRequest,
+        ctx: &AuditContext,
+    ) -> Result<(User, UserEvent), StoreError> {
+        let (user, event) = self.inner.create_user(input, ctx).await?;
+        self.record(&event);
+        Ok((user, event))
+    }
+
+    async fn create_users(
+        &self,
+        inputs: &[CreateUserRequest],
+        ctx: &AuditContext,
+    ) -> Result<Vec<CreateOutcome>, StoreError> {
+        let outcomes = self.inner.create_users(inputs, ctx).await?;
+        for (_, event) in outcomes.iter().flatten() {
+            self.record(event);
+        }
+        Ok(outcomes)
+    }
+
+    async fn get_user(&self, id: i32) -> Result<Option<User>, StoreError> {
+        self.inner.get_user(id).await
+    }
+
+    async fn list_users(&self, query: &UserQuery) -> Result<Vec<User>, StoreError> {
+        self.inner.list_users(query).await
+    }
+
+    async fn replace_user(
+        &self,
+        id: i32,
+        input: &CreateUserRequest,
+        precondition: &Precondition,
+        ctx: &AuditContext,
+    ) -> Result<Option<(User, UserEvent)>, StoreError> {
+        let replaced = self
+            .inner
+            .replace_user(id, input, precondition, ctx)
+            .await?;
+        if let Some((_, event)) = &replaced {
+            self.record(event);
+        }
+        Ok(replaced)
+    }
+
+    async fn delete_user(
+        &self,
+        id: i32,
+        precondition: &Precondition,
+        ctx: &AuditContext,
+    ) -> Result<Option<UserEvent>, StoreError> {
+        let event = self.inner.delete_user(id, precondition, ctx).await?;
+        if let Some(event) = &event {
+            self.record(event);
+        }
+        Ok(event)
+    }
+
+    async fn restore_user(
+        &self,
+        id: i32,
+        ctx: &AuditContext,
+    ) -> Result<Option<(User, UserEvent)>, StoreError> {
+        let restored = self.inner.restore_user(id, ctx).await?;
+        if let Some((_, event)) = &restored {
+            self.record(event);
+        }
+        Ok(restored)
+    }
+
+    async fn purge_deleted(
+        &self,
+        deleted_before: DateTime<Utc>,
+        ctx: &AuditContext,
+    ) -> Result<u64, StoreError> {
+        self.inner.purge_deleted(deleted_before, ctx).await
+    }
+
+    async fn user_history(
+        &self,
+        user_id: i32,
+        query: &HistoryQuery,
+    ) -> Result<Vec<AuditEntry>, StoreError> {
+        self.inner.user_history(user_id, query).await
+    }
+}
+
+// 重放缓冲区只保留最近 3 个事件，写入 5 个用户后缓冲区中是 seq 3..=5
+fn app(store: &RecordingStore) -> Router {
+    user_router(AppState::new(store.clone()).event_replay_capacity(3))
+}
+
+async fn send(app: &Router, method: Method, uri: &str, body: Value) -> StatusCode {
+    let request = Request::builder()
+        .method(method)
+        .uri(uri)
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(Body::from(body.to_string()))
+        .unwrap();
+    app.clone().oneshot(request).await.unwrap().status()
+}
+
+async fn create_users(app: &Router, count: usize) {
+    for index in 1..=count {
+        let body = json!({
+            "name": format!("user-{}", index),
+            "email": format!("user-{}@stream.test", index),
+        });
+        assert_eq!(
+            send(app, Method::POST, "/api/users", body).await,
+            StatusCode::OK
+        );
+    }
+}
+
+#[derive(Debug)]
+struct SseEvent {
+    id: Option<String>,
+    event: String,
+    data: Value,
+}
+
+// 逐个读取 SSE 事件，跳过保活注释
+struct SseReader {
+    body: BodyDataStream,
+    buffer: String,
+}
+
+impl SseReader {
+    async fn open(app: &Router, last_event_id: Option<&str>) -> Self {
+        let mut request = Request::builder().uri("/api/users/events");
+        if let Some(id) = last_event_id {
+            request = request.header("last-event-id", id);
+        }
+        let response = app
+            .clone()
+            .oneshot(request.body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        SseReader {
+            body: response.into_body().into_data_stream(),
+            buffer: String::new(),
+        }
+    }
+
+    async fn next(&mut self) -> SseEvent {
+        loop {
+            if let Some(end) = self.buffer.find("\n\n") {
+                let frame: String = self.buffer.drain(..end + 2).collect();
+                let mut id = None;
+                let mut event = None;
+                let mut data = None;
+                for line in frame.lines() {
+                    match line.split_once(':') {
+                        Some(("id", value)) => id = Some(value.trim().to_string()),
+                        Some(("event", value)) => event = Some(value.trim().to_string()),
+                        Some(("data", value)) => data = Some(value.trim().to_string()),
+                        _ => {}
+                    }
+                }
+                if let (Some(event), Some(data)) = (event, data) {
+                    return SseEvent {
+                        id,
+                        event,
+                        data: serde_json::from_str(&data).unwrap(),
+                    };
+                }
+                continue;
+            }
+            let chunk = tokio::time::timeout(Duration::from_secs(5), self.body.next())
+                .await
+                .expect("timed out waiting for an event")
+                .expect("the event stream ended")
+                .unwrap();
+            self.buffer.push_str(std::str::from_utf8(&chunk).unwrap());
+        }
+    }
+}
+
+#[tokio::test]
+async fn sse_replays_events_after_last_event_id() {
+    let store = RecordingStore::default();
+    let app = app(&store);
+    create_users(&app, 5).await;
+    let ids = store.event_ids();
+
+    // 推送的事件 id 与存储返回（即写入 outbox）的事件 id 相同
+    let mut events = SseReader::open(&app, Some("3")).await;
+    for (seq, id) in [(4, &ids[3]), (5, &ids[4])] {
+        let event = events.next().await;
+        assert_eq!(event.id, Some(seq.to_string()));
+        assert_eq!(event.event, "user.created");
+        assert_eq!(event.data["id"], id.as_str());
+        assert_eq!(event.data["type"], "user.created");
+    }
+
+    // 补发之后接着推送实时事件
+    let replaced = json!({ "name": "renamed", "email": "user-1@stream.test" });
+    assert_eq!(
+        send(&app, Method::PUT, "/api/users/1", replaced).await,
+        StatusCode::OK
+    );
+    let event = events.next().await;
+    assert_eq!(event.id, Some("6".to_string()));
+    assert_eq!(event.event, "user.updated");
+    assert_eq!(event.data["id"], store.event_ids()[5].as_str());
+    assert_eq!(event.data["data"]["name"], "renamed");
+}
+
+#[tokio::test]
+async fn sse_resets_when_last_event_id_is_not_in_the_buffer() {
+    let store = RecordingStore::default();
+    let app = app(&store);
+    create_users(&app, 5).await;
+
+    // seq 2 已被挤出缓冲区；seq 99 来自重启前的进程
+    for last_event_id in ["1", "99"] {
+        let mut events = SseReader::open(&app, Some(last_event_id)).await;
+        let reset = events.next().await;
+        assert_eq!(reset.id, None);
+        assert_eq!(reset.event, "reset");
+        assert_eq!(reset.data, json!({ "type": "reset" }));
+    }
+
+    // 缓冲区中最早的事件之前的 id 仍可补齐
+    let mut events = SseReader::open(&app, Some("2")).await;
+    assert_eq!(events.next().await.id, Some("3".to_string()));
+
+    let request = Request::builder()
+        .uri("/api/users/events")
+        .header("last-event-id", "not-a-number")
+        .body(Body::empty())
+        .unwrap();
+    let response = app.clone().oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
+
+type Socket = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+// 下一条文本消息的 JSON
+async fn next_message(socket: &mut Socket) -> Value {
+    match tokio::time::timeout(Duration::from_secs(5), socket.next())
+        .await
+        .expect("timed out waiting for a message")
+    {
+        Some(Ok(Message::Text(text))) => serde_json::from_str(&text).unwrap(),
+        other => panic!("unexpected message: {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn websocket_replays_events_and_resets_on_gaps() {
+    let store = RecordingStore::default();
+    let app = app(&store);
+    create_users(&app, 5).await;
+    let ids = store.event_ids();
+
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let server = app.clone();
+    tokio::spawn(async move { axum::serve(listener, server).await });
+
+    let connect = |last_event_id: &str| {
+        let url = format!(
+            "ws://{}/api/users/events/ws?last_event_id={}",
+            addr, last_event_id
+        );
+        async move { tokio_tungstenite::connect_async(url).await.unwrap().0 }
+    };
+    let mut socket = connect("3").await;
+    for (seq, id) in [(4, &ids[3]), (5, &ids[4])] {
+        let message = next_message(&mut socket).await;
+        assert_eq!(message["seq"], seq);
+        assert_eq!(message["type"], "user.created");
+        assert_eq!(message["payload"]["id"], id.as_str());
+    }
+
+    let mut stale = connect("1").await;
+    assert_eq!(next_message(&mut stale).await, json!({ "type": "reset" }));
+
+    assert_eq!(
+        send(&app, Method::DELETE, "/api/users/2", Value::Null).await,
+        StatusCode::NO_CONTENT
+    );
+    let deleted_id = store.event_ids()[5].clone();
+    for socket in [&mut socket, &mut stale] {
+        let message = next_message(socket).await;
+        assert_eq!(message["seq"], 6);
+        assert_eq!(message["type"], "user.deleted");
+        assert_eq!(message["payload"]["id"], deleted_id.as_str());
+    }
+    socket.close(None).await.unwrap();
+}
@@ -0,0 +1,134 @@
+use axum::{
+    body::Body,
+    http::{header, Method, Request, Response, StatusCode},
+    Router,
+};
+use serde_json::json;
+use tower::ServiceExt;
+
+use hello_rust::config::Config;
+use hello_rust::limits::Limits;
+use hello_rust::user::{user_router, AppState, InMemoryUserStore};
+use hello_rust::web::WebLayers;
+
+const ALLOWED: &str = "https://app.example.com";
+
+// 与服务端二进制相同的挂载顺序：限流在内，CORS 等响应头在最外层
+fn app() -> Router {
+    let mut config = Config::default();
+    config.http.cors.allowed_origins = vec![ALLOWED.to_string()];
+    let users =
+        Limits::new(&config.limits).apply(user_router(AppState::new(InMemoryUserStore::new())));
+    WebLayers::new(&config.http).apply(users)
+}
+
+async fn send(app: &Router, request: Request<Body>) -> Response<Body> {
+    app.clone().oneshot(request).await.unwrap()
+}
+
+fn preflight(origin: &str) -> Request<Body> {
+    Request::builder()
+        .method(Method::OPTIONS)
+        .uri("/api/users")
+        .header(header::ORIGIN, origin)
+        .header(header::ACCESS_CONTROL_REQUEST_METHOD, "POST")
+        .header(
+            header::ACCESS_CONTROL_REQUEST_HEADERS,
+            "content-type,if-match",
+        )
+        .body(Body::empty())
+        .unwrap()
+}
+
+fn header(response: &Response<Body>, name: header::HeaderName) -> Option<&str> {
+    response
+        .headers()
+        .get(name)
+        .map(|value| value.to_str().unwrap())
+}
+
+// 逗号分隔的响应头值
+fn header_list(response: &Response<Body>, name: header::HeaderName) -> Vec<String> {
+    header(response, name)
+        .unwrap_or_default()
+        .split(',')
+        .map(|item| item.trim().to_ascii_lowercase())
+        .collect()
+}
+
+#[tokio::test]
+async fn preflight_echoes_allowed_origins_only() {
+    let app = app();
+
+    let allowed = send(&app, preflight(ALLOWED)).await;
+    assert_eq!(allowed.status(), StatusCode::OK);
+    assert_eq!(
+        header(&allowed, header::ACCESS_CONTROL_ALLOW_ORIGIN),
+        Some(ALLOWED)
+    );
+    assert!(
+        header_list(&allowed, header::ACCESS_CONTROL_ALLOW_METHODS).contains(&"post".to_string())
+    );
+    let allowed_headers = header_list(&allowed, header::ACCESS_CONTROL_ALLOW_HEADERS);
+    for name in ["content-type", "if-match"] {
+        assert!(allowed_headers.contains(&name.to_string()), "{}", name);
+    }
+    assert_eq!(
+        header(&allowed, header::ACCESS_CONTROL_MAX_AGE),
+        Some("600")
+    );
+    // 预检由 CORS 层直接应答，不消耗限流配额
+    assert!(allowed.headers().get("ratelimit-limit").is_none());
+
+    // 未配置的来源拿不到 Access-Control-Allow-Origin，浏览器会拦截后续请求
+    let rejected = send(&app, preflight("https://evil.example.com")).await;
+    assert!(rejected
+        .headers()
+        .get(header::ACCESS_CONTROL_ALLOW_ORIGIN)
+        .is_none());
+    assert!(rejected
+        .headers()
+        .get(header::ACCESS_CONTROL_ALLOW_CREDENTIALS)
+        .is_none());
+}
+
+#[tokio::test]
+async fn cross_origin_responses_expose_etag_and_rate_limit_headers() {
+    let app = app();
+    let create = Request::builder()
+        .method(Method::POST)
+        .uri("/api/users")
+        .header(header::ORIGIN, ALLOWED)
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(Body::from(
+            json!({ "name": "Cors", "email": "cors@web.test" }).to_string(),
+        ))
+        .unwrap();
+    assert_eq!(send(&app, create).await.status(), StatusCode::OK);
+
+    let request = Request::builder()
+        .uri("/api/users/1")
+        .header(header::ORIGIN, ALLOWED)
+        .body(Body::empty())
+        .unwrap();
+    let response = send(&app, request).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(
+        header(&response, header::ACCESS_CONTROL_ALLOW_ORIGIN),
+        Some(ALLOWED)
+    );
+    assert!(header_list(&response, header::VARY).contains(&"origin".to_string()));
+
+    // 暴露给脚本的响应头确实出现在响应上
+    let exposed = header_list(&response, header::ACCESS_CONTROL_EXPOSE_HEADERS);
+    for name in [
+        "etag",
+        "ratelimit-limit",
+        "ratelimit-remaining",
+        "ratelimit-reset",
+    ] {
+        assert!(exposed.contains(&name.to_string()), "{}", name);
+        assert!(response.headers().contains_key(name), "{}", name);
+    }
+    assert!(exposed.contains(&"retry-after".to_string()));
+}
@@ -0,0 +1,275 @@
+use std::{
+    collections::HashMap,
+    env,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use axum::{
+    body::Bytes,
+    extract::{Path, State},
+    http::{HeaderMap, HeaderValue, StatusCode},
+    routing::post,
+    Router,
+};
+use chrono::Utc;
+use serde_json::Value;
+use sqlx::PgPool;
+use tokio::net::TcpListener;
+use uuid::Uuid;
+
+use hello_rust::config::{WebhookConfig, WebhookEndpoint};
+use hello_rust::migrate;
+use hello_rust::user::{AuditContext, CreateUserRequest, UserRepository, UserStore};
+use hello_rust::webhook::{
+    sign, verify, VerifyError, WebhookDispatcher, DEFAULT_TOLERANCE, WEBHOOK_ID, WEBHOOK_SIGNATURE,
+    WEBHOOK_TIMESTAMP,
+};
+
+const SECRET: &[u8] = b"whsec-test-secret-0123456789";
+const BODY: &[u8] = br#"{"type":"user.created","data":{"id":1}}"#;
+
+fn signed_headers(id: &str, timestamp: i64, signature: &str) -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    headers.insert(WEBHOOK_ID, HeaderValue::from_str(id).unwrap());
+    headers.insert(WEBHOOK_TIMESTAMP, HeaderValue::from(timestamp));
+    headers.insert(WEBHOOK_SIGNATURE, HeaderValue::from_str(signature).unwrap());
+    headers
+}
+
+#[test]
+fn signature_round_trips() {
+    let timestamp = Utc::now().timestamp();
+    let signature = sign(SECRET, "msg_1", timestamp, BODY);
+    assert!(signature.starts_with("v1,"));
+
+    let headers = signed_headers("msg_1", timestamp, &signature);
+    verify(SECRET, &headers, BODY, DEFAULT_TOLERANCE).unwrap();
+
+    // 轮换密钥期间可同时携带新旧签名
+    let rotated = format!(
+        "{} {}",
+        sign(b"an-older-secret-value", "msg_1", timestamp, BODY),
+        signature
+    );
+    let headers = signed_headers("msg_1", timestamp, &rotated);
+    verify(SECRET, &headers, BODY, DEFAULT_TOLERANCE).unwrap();
+}
+
+#[test]
+fn tampered_requests_are_rejected() {
+    let timestamp = Utc::now().timestamp();
+    let signature = sign(SECRET, "msg_1", timestamp, BODY);
+    let headers = signed_headers("msg_1", timestamp, &signature);
+
+    let tampered = br#"{"type":"user.deleted","data":{"id":1}}"#;
+    assert!(matches!(
+        verify(SECRET, &headers, tampered, DEFAULT_TOLERANCE),
+        Err(VerifyError::SignatureMismatch)
+    ));
+    assert!(matches!(
+        verify(
+            b"some-other-secret-value",
+            &headers,
+            BODY,
+            DEFAULT_TOLERANCE
+        ),
+        Err(VerifyError::SignatureMismatch)
+    ));
+
+    // 签名覆盖 id 和时间戳
+    let replayed = signed_headers("msg_2", timestamp, &signature);
+    assert!(matches!(
+        verify(SECRET, &replayed, BODY, DEFAULT_TOLERANCE),
+        Err(VerifyError::SignatureMismatch)
+    ));
+
+    let mut unsigned = headers.clone();
+    unsigned.remove(WEBHOOK_SIGNATURE);
+    assert!(matches!(
+        verify(SECRET, &unsigned, BODY, DEFAULT_TOLERANCE),
+        Err(VerifyError::MissingHeader(WEBHOOK_SIGNATURE))
+    ));
+}
+
+#[test]
+fn timestamps_outside_the_tolerance_are_rejected() {
+    let tolerance = Duration::from_secs(60);
+    let now = Utc::now().timestamp();
+    for timestamp in [now - 120, now + 120] {
+        let signature = sign(SECRET, "msg_1", timestamp, BODY);
+        let headers = signed_headers("msg_1", timestamp, &signature);
+        assert!(matches!(
+            verify(SECRET, &headers, BODY, tolerance),
+            Err(VerifyError::TimestampOutOfTolerance)
+        ));
+    }
+
+    let timestamp = now - 30;
+    let signature = sign(SECRET, "msg_1", timestamp, BODY);
+    let headers = signed_headers("msg_1", timestamp, &signature);
+    verify(SECRET, &headers, BODY, tolerance).unwrap();
+
+    let mut headers = headers;
+    headers.insert(WEBHOOK_TIMESTAMP, HeaderValue::from_static("yesterday"));
+    assert!(matches!(
+        verify(SECRET, &headers, BODY, tolerance),
+        Err(VerifyError::InvalidTimestamp)
+    ));
+}
+
+// 每个端点、每个 webhook-id 收到的请求体
+type Received = HashMap<(String, String), Vec<Value>>;
+
+// 进程内的接收方：校验签名，前 failures 次请求返回 503，之后返回 204
+#[derive(Clone, Default)]
+struct Receiver {
+    received: Arc<Mutex<Received>>,
+}
+
+async fn receive(
+    State(receiver): State<Receiver>,
+    Path((endpoint, failures)): Path<(String, usize)>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> StatusCode {
+    if verify(SECRET, &headers, &body, DEFAULT_TOLERANCE).is_err() {
+        return StatusCode::UNAUTHORIZED;
+    }
+    let id = headers[WEBHOOK_ID].to_str().unwrap().to_string();
+    let mut received = receiver.received.lock().unwrap();
+    let attempts = received.entry((endpoint, id)).or_default();
+    attempts.push(serde_json::from_slice(&body).unwrap());
+    if attempts.len() <= failures {
+        StatusCode::SERVICE_UNAVAILABLE
+    } else {
+        StatusCode::NO_CONTENT
+    }
+}
+
+// 需要 Postgres：设置 DATABASE_URL 时运行。分发器只领取本次运行配置的端点上的投递，
+// 断言只针对本次写入的事件
+#[tokio::test]
+async fn dispatcher_retries_and_marks_exhausted_deliveries_dead() {
+    let Ok(url) = env::var("DATABASE_URL") else {
+        eprintln!("DATABASE_URL is not set, skipping the webhook dispatcher test");
+        return;
+    };
+    let pool = PgPool::connect(&url)
+        .await
+        .expect("failed to connect to DATABASE_URL");
+    migrate::up(&pool).await.expect("failed to run migrations");
+
+    let receiver = Receiver::default();
+    let app = Router::new()
+        .route("/hooks/{endpoint}/{failures}", post(receive))
+        .with_state(receiver.clone());
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, app).await });
+
+    let run = Uuid::new_v4().simple().to_string();
+    let endpoint = |name: &str, failures: usize| WebhookEndpoint {
+        name: format!("{}-{}", name, run),
+        url: format!("http://{}/hooks/{}/{}", addr, name, failures),
+        secret: String::from_utf8(SECRET.to_vec()).unwrap(),
+        events: vec!["user.created".to_string()],
+    };
+    let config = WebhookConfig {
+        // 第一次失败后重试成功；始终失败的端点在第 3 次后标记为 dead
+        endpoints: vec![endpoint("flaky", 1), endpoint("down", usize::MAX)],
+        poll_interval_ms: 10,
+        request_timeout_ms: 2000,
+        max_attempts: 3,
+        backoff_base_secs: 0,
+        backoff_max_secs: 0,
+        batch_size: 500,
+    };
+    let dispatcher = WebhookDispatcher::new(pool.clone(), &config);
+
+    // 库中可能留有其他测试写入的大量未分发事件（如批量导入），先用不配置端点的分发器
+    // 把它们标记为已分发，避免本次的端点逐个投递这些事件
+    let idle = WebhookDispatcher::new(
+        pool.clone(),
+        &WebhookConfig {
+            endpoints: Vec::new(),
+            ..config.clone()
+        },
+    );
+    while idle.run_once().await.unwrap() > 0 {}
+
+    let store = UserRepository::new(pool.clone());
+    let input = CreateUserRequest {
+        name: "Webhook".to_string(),
+        email: format!("webhook@{}.test", run),
+    };
+    let (user, event) = store
+        .create_user(&input, &AuditContext::system("webhook-test"))
+        .await
+        .unwrap();
+
+    // 其他实例配置的端点上的投递不能被本实例领取
+    let foreign = format!("elsewhere-{}", run);
+    sqlx::query(
+        "INSERT INTO webhook_deliveries (outbox_id, endpoint) \
+         SELECT id, $2 FROM user_outbox WHERE event_id = $1",
+    )
+    .bind(event.id)
+    .bind(&foreign)
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    let flaky = format!("flaky-{}", run);
+    let down = format!("down-{}", run);
+    let mut deliveries = HashMap::new();
+    for _ in 0..50 {
+        dispatcher.run_once().await.unwrap();
+        deliveries = delivery_states(&pool, event.id).await;
+        let settled = [&flaky, &down].iter().all(|endpoint| {
+            deliveries
+                .get(*endpoint)
+                .is_some_and(|(status, _)| status != "pending")
+        });
+        if settled {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+
+    assert_eq!(deliveries.get(&flaky), Some(&("delivered".to_string(), 2)));
+    assert_eq!(deliveries.get(&down), Some(&("dead".to_string(), 3)));
+    assert_eq!(deliveries.get(&foreign), Some(&("pending".to_string(), 0)));
+
+    let received = receiver.received.lock().unwrap();
+    let flaky = &received[&("flaky".to_string(), event.id.to_string())];
+    assert_eq!(flaky.len(), 2);
+    assert_eq!(flaky[1]["id"], event.id.to_string());
+    assert_eq!(flaky[1]["type"], "user.created");
+    assert_eq!(flaky[1]["data"]["id"], user.id);
+    // 重试发送的是同一个事件
+    assert_eq!(flaky[0], flaky[1]);
+    assert_eq!(
+        received[&("down".to_string(), event.id.to_string())].len(),
+        3
+    );
+}
+
+// 事件在各端点上的投递状态和尝试次数
+async fn delivery_states(pool: &PgPool, event_id: Uuid) -> HashMap<String, (String, i32)> {
+    sqlx::query_as::<_, (String, String, i32)>(
+        r#"
+        SELECT d.endpoint, d.status, d.attempts
+        FROM webhook_deliveries AS d
+        JOIN user_outbox AS o ON o.id = d.outbox_id
+        WHERE o.event_id = $1
+        "#,
+    )
+    .bind(event_id)
+    .fetch_all(pool)
+    .await
+    .unwrap()
+    .into_iter()
+    .map(|(endpoint, status, attempts)| (endpoint, (status, attempts)))
+    .collect()
+}